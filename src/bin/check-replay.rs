@@ -0,0 +1,78 @@
+// usage: check-replay <level or directory>...
+// plays the replay next to every level (level1.json.replay) without a window and reports whether the chest opens
+// directories are searched for levels that have a replay, exits with 1 if any replay fails
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use nordic_grid::level_manager::read_level;
+use nordic_grid::replay::{self, replay_path};
+
+
+// the levels to check, in directories only the ones with a replay (not recursively)
+fn collect_levels(arg: &str, levels: &mut Vec<PathBuf>) -> std::io::Result<()> {
+	let path = Path::new(arg);
+
+	if !path.is_dir() {
+		levels.push(path.to_path_buf());
+		return Ok(());
+	}
+
+	let mut found: Vec<PathBuf> = fs::read_dir(path)?
+		.filter_map(|entry| entry.ok().map(|e| e.path()))
+		.filter(|p| p.extension().is_some_and(|ext| ext != replay::REPLAY_EXTENSION) && replay_path(p).exists())
+		.collect();
+
+	found.sort();
+	levels.append(&mut found);
+
+	Ok(())
+}
+
+// Ok(false) if the replay runs through, but the chest stays closed
+fn check(level_path: &Path) -> Result<(bool, usize), Box<dyn std::error::Error>> {
+	let level = read_level(level_path)?;
+	let replay = replay::read_replay(replay_path(level_path))?;
+
+	let rules = replay::run(&level, &replay)?;
+
+	Ok((rules.completed, replay.steps.len()))
+}
+
+fn main() -> ExitCode {
+	let args: Vec<String> = std::env::args().skip(1).collect();
+
+	if args.is_empty() {
+		eprintln!("usage: check-replay <level or directory>...");
+		return ExitCode::FAILURE;
+	}
+
+	let mut levels = vec!();
+	for arg in &args {
+		if let Err(e) = collect_levels(arg, &mut levels) {
+			eprintln!("{}: {}", arg, e);
+			return ExitCode::FAILURE;
+		}
+	}
+
+	let mut failed = 0;
+
+	for level in &levels {
+		match check(level) {
+			Ok((true, steps)) => println!("{}: the chest opens after {} steps", level.display(), steps),
+			Ok((false, steps)) => {
+				failed += 1;
+				println!("{}: the chest stays closed after {} steps", level.display(), steps);
+			},
+			Err(e) => {
+				failed += 1;
+				println!("{}: {}", level.display(), e);
+			},
+		}
+	}
+
+	println!("{} replays checked, {} failed", levels.len(), failed);
+
+	if failed > 0 { ExitCode::FAILURE } else { ExitCode::SUCCESS }
+}
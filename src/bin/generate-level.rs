@@ -0,0 +1,67 @@
+// usage: generate-level [--width 7] [--height 7] [--difficulty 0.5] [--cards 8] [--seed <number>] [--out <level.json>]
+// writes a solvable level to --out or prints it
+
+use std::process::ExitCode;
+
+use nordic_grid::generator::{self, Settings};
+use nordic_grid::level_manager::{level_to_json, write_level};
+
+
+fn arg_value<'a>(args: &'a [String], name: &str) -> Option<&'a String> {
+	args.iter()
+		.position(|arg| arg == name)
+		.and_then(|i| args.get(i + 1))
+}
+
+fn parse_arg<T: std::str::FromStr>(args: &[String], name: &str, default: T) -> Result<T, String> {
+	match arg_value(args, name) {
+		Some(value) => value.parse::<T>().map_err(|_| format!("{} can't be \"{}\"", name, value)),
+		None => Ok(default),
+	}
+}
+
+fn main() -> ExitCode {
+	let args: Vec<String> = std::env::args().collect();
+
+	let settings = (|| -> Result<Settings, String> {
+		Ok(Settings {
+			width: parse_arg(&args, "--width", 7)?,
+			height: parse_arg(&args, "--height", 7)?,
+			difficulty: parse_arg(&args, "--difficulty", 0.5)?,
+			max_cards: parse_arg(&args, "--cards", 8)?,
+			seed: parse_arg(&args, "--seed", rand::random())?,
+		})
+	})();
+
+	let settings = match settings {
+		Ok(settings) if settings.max_cards > generator::MAX_CARDS => {
+			eprintln!("--cards can't be more than {}", generator::MAX_CARDS);
+			return ExitCode::FAILURE;
+		},
+		Ok(settings) => settings,
+		Err(e) => {
+			eprintln!("{}", e);
+			return ExitCode::FAILURE;
+		}
+	};
+
+	let Some(generated) = generator::generate(&settings) else {
+		eprintln!("no solvable {}x{} level with at most {} cards found (seed {})", settings.width, settings.height, settings.max_cards, settings.seed);
+		return ExitCode::FAILURE;
+	};
+
+	let shortest = generated.report.card_plays().unwrap_or(0);
+	eprintln!("seed {}: {} attempts, shortest solution uses {} cards, {} distinct solutions", settings.seed, generated.attempts, shortest, generated.report.solutions);
+
+	match arg_value(&args, "--out") {
+		Some(path) => {
+			if let Err(e) = write_level(path, &generated.level) {
+				eprintln!("{}: {}", path, e);
+				return ExitCode::FAILURE;
+			}
+		},
+		None => print!("{}", level_to_json(&generated.level)),
+	}
+
+	ExitCode::SUCCESS
+}
@@ -0,0 +1,91 @@
+// usage: solve-level <level.json or snapshot.json> [--seed <number>]
+// prints the shortest solution and how many solutions there are, exits with 1 if there is none
+// snapshots are solved from the situation they were taken in
+
+use std::fs;
+use std::process::ExitCode;
+
+use nordic_grid::level_manager::read_level;
+use nordic_grid::rules::Rules;
+use nordic_grid::snapshot::parse_snapshot;
+use nordic_grid::solver;
+
+
+fn main() -> ExitCode {
+	let args: Vec<String> = std::env::args().collect();
+
+	let Some(path) = args.get(1) else {
+		eprintln!("usage: solve-level <level.json or snapshot.json> [--seed <number>]");
+		return ExitCode::FAILURE;
+	};
+
+	let seed_override = args.iter()
+		.position(|arg| arg == "--seed")
+		.and_then(|i| args.get(i + 1))
+		.map(|seed| seed.parse::<u64>().expect("--seed needs a number"));
+
+	let (rules, shuffled) = match load(path, seed_override) {
+		Ok(loaded) => loaded,
+		Err(e) => {
+			eprintln!("{}: {}", path, e);
+			return ExitCode::FAILURE;
+		}
+	};
+
+	let report = solver::solve(&rules);
+
+	if shuffled {
+		println!("{} (seed {})", path, rules.seed);
+	} else {
+		println!("{}", path);
+	}
+
+	let Some(steps) = &report.shortest else {
+		println!("not solvable, {} states searched", report.states);
+		return ExitCode::FAILURE;
+	};
+
+	let card_plays = solver::card_plays(steps);
+	match steps.len() - card_plays {
+		0 => println!("solvable with {} card plays, {} distinct solutions, {} states searched", card_plays, report.solutions, report.states),
+		turns => println!("solvable with {} card plays and {} ended turns, {} distinct solutions, {} states searched", card_plays, turns, report.solutions, report.states),
+	}
+
+	for (i, step) in steps.iter().enumerate() {
+		let Some(card_type) = step.card else {
+			println!("  {}. end the turn", i + 1);
+			continue;
+		};
+
+		let card = match step.hand_index {
+			Some(idx) => format!("{} (hand card {})", card_type, idx + 1),
+			None => format!("{} (already played)", card_type),
+		};
+
+		match step.target {
+			Some((x, y)) => println!("  {}. {} on ({}, {})", i + 1, card, x, y),
+			None => println!("  {}. {}", i + 1, card),
+		}
+	}
+
+	ExitCode::SUCCESS
+}
+
+// snapshots are told apart from levels by their "rules"
+fn load(path: &str, seed_override: Option<u64>) -> Result<(Rules, bool), Box<dyn std::error::Error>> {
+	let text = fs::read_to_string(path)?;
+
+	let is_snapshot = serde_json::from_str::<serde_json::Value>(&text)
+		.map(|value| value.get("rules").is_some())
+		.unwrap_or(false);
+
+	if is_snapshot {
+		let snapshot = parse_snapshot(&text)?;
+		return Ok((snapshot.rules, snapshot.level.shuffle));
+	}
+
+	let level = read_level(path)?;
+	let seed = level.choose_seed(seed_override);
+
+	Ok((level.rules(seed), level.shuffle))
+}
@@ -0,0 +1,63 @@
+// usage: validate-level <level.json, level.txt, map.tmj or directory>...
+// prints every problem as path:line: message and exits with 1 if there was any
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use nordic_grid::validator;
+
+
+// the files to check, directories are searched for .json, .txt and .tmj files (not recursively)
+fn collect_files(arg: &str, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+	let path = Path::new(arg);
+
+	if !path.is_dir() {
+		files.push(path.to_path_buf());
+		return Ok(());
+	}
+
+	let mut found: Vec<PathBuf> = fs::read_dir(path)?
+		.filter_map(|entry| entry.ok().map(|e| e.path()))
+		.filter(|p| p.extension().is_some_and(|ext| ext == "json" || ext == "txt" || ext == "tmj"))
+		.collect();
+
+	found.sort();
+	files.append(&mut found);
+
+	Ok(())
+}
+
+fn main() -> ExitCode {
+	let args: Vec<String> = std::env::args().skip(1).collect();
+
+	if args.is_empty() {
+		eprintln!("usage: validate-level <level.json, level.txt, map.tmj or directory>...");
+		return ExitCode::FAILURE;
+	}
+
+	let mut files = vec!();
+	for arg in &args {
+		if let Err(e) = collect_files(arg, &mut files) {
+			eprintln!("{}: {}", arg, e);
+			return ExitCode::FAILURE;
+		}
+	}
+
+	let mut problem_count = 0;
+
+	for file in &files {
+		for problem in validator::validate_file(file) {
+			problem_count += 1;
+
+			match problem.line {
+				Some(line) => println!("{}:{}: {}", file.display(), line, problem.message),
+				None => println!("{}: {}", file.display(), problem.message),
+			}
+		}
+	}
+
+	println!("{} files checked, {} problems", files.len(), problem_count);
+
+	if problem_count > 0 { ExitCode::FAILURE } else { ExitCode::SUCCESS }
+}
@@ -0,0 +1,74 @@
+// things on the grid that only the Attack card can destroy: training dummies and crates
+// they block the way like trees until their health is gone, headless like Rules
+
+use serde::{Serialize, Deserialize};
+
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum BreakableKind {
+	Dummy,			// takes a few hits
+	Crate,			// breaks on the first hit
+}
+
+impl BreakableKind {
+	// used if the level doesn't give a health
+	pub fn default_health(self: BreakableKind) -> f32 {
+		match self {
+			BreakableKind::Dummy => 4.0,
+			BreakableKind::Crate => 1.0,
+		}
+	}
+
+	// text levels and Tiled maps, case doesn't matter
+	pub fn from_name(name: &str) -> Option<BreakableKind> {
+		match name.trim().to_lowercase().as_str() {
+			"dummy" => Some(BreakableKind::Dummy),
+			"crate" => Some(BreakableKind::Crate),
+			_ => None,
+		}
+	}
+}
+
+// a breakable as the level file places it
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct BreakableSpawn {
+	pub kind: BreakableKind,
+	pub x: i32,
+	pub y: i32,
+	#[serde(default)]
+	pub health: Option<f32>,
+}
+
+impl BreakableSpawn {
+	pub fn breakable(self: &BreakableSpawn) -> Breakable {
+		let health = self.health.unwrap_or_else(|| self.kind.default_health());
+
+		Breakable {
+			kind: self.kind,
+			pos: (self.x, self.y),
+			health,
+			max_health: health,
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
+pub struct Breakable {
+	pub kind: BreakableKind,
+	pub pos: (i32, i32),
+	pub health: f32,
+	pub max_health: f32,			// for drawing how damaged it is
+}
+
+impl Breakable {
+	// returns true if it broke, Rules removes broken ones
+	pub fn damage(self: &mut Breakable, amount: f32) -> bool {
+		self.health = (self.health - amount.max(0.0)).max(0.0);
+		self.is_broken()
+	}
+
+	pub fn is_broken(self: &Breakable) -> bool {
+		self.health <= 0.0
+	}
+}
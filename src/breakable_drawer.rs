@@ -0,0 +1,171 @@
+// draws the dummies and crates in Rules with their health above them,
+// hits make them shake and flash, broken ones fade out
+
+use ggez::{
+	graphics::*,
+	glam::*,
+	Context, GameResult
+};
+
+use crate::breakable::{Breakable, BreakableKind};
+use crate::grid::Grid;
+use crate::grid_drawer as GridDrawer;
+use crate::math::{lerp, sin};
+use crate::rules::Rules;
+
+use std::f32::consts::PI as PI;
+
+
+const HIT_TIME: f32 = 0.6;				// seconds a hit is shown
+const SHAKE_FREQ: f32 = 12.0;
+const SHAKE_AMP: f32 = 0.08;			// in fields
+const RISE: f32 = 12.0;					// how far the damage number floats up, before scaling
+const HIT_COLOR: Color = Color::new(1.0, 0.35, 0.25, 1.0);
+const LABEL_Z: i32 = 1500;				// above every figurine, like the intents of the enemies
+
+
+// dummies use the player sprite and crates the chest sprite, in their own colors
+pub fn kind_color(kind: BreakableKind) -> Color {
+	match kind {
+		BreakableKind::Dummy => Color::from_rgb(210, 180, 110),
+		BreakableKind::Crate => Color::from_rgb(150, 100, 60),
+	}
+}
+
+// one hit on one breakable, started by hit()
+struct HitEffect {
+	kind: BreakableKind,
+	pos: (i32, i32),
+	amount: f32,
+	broken: bool,
+	time: f32,
+}
+
+pub struct BreakableDrawer {
+	dummy_sprite: Image,
+	crate_sprite: Image,
+	hits: Vec<HitEffect>,
+}
+
+impl BreakableDrawer {
+	pub fn new(ctx: &mut Context) -> BreakableDrawer {
+		BreakableDrawer {
+			dummy_sprite: Image::from_path(ctx, "/player.png").unwrap(),
+			crate_sprite: Image::from_path(ctx, "/chest.png").unwrap(),
+			hits: vec!(),
+		}
+	}
+
+	// compares the breakables before and after an attack, every one that lost health gets a hit
+	pub fn hit(self: &mut BreakableDrawer, before: &Rules, after: &Rules) {
+		for old in &before.breakables {
+			let health = after.breakables.iter().find(|breakable| breakable.pos == old.pos).map_or(0.0, |breakable| breakable.health);
+
+			if health < old.health {
+				self.hits.push(HitEffect { kind: old.kind, pos: old.pos, amount: old.health - health, broken: health <= 0.0, time: 0.0 });
+			}
+		}
+	}
+
+	// e.g. when a level starts, old hits don't belong to the new grid
+	pub fn clear(self: &mut BreakableDrawer) {
+		self.hits.clear();
+	}
+
+	pub fn update(self: &mut BreakableDrawer, dt: f32) {
+		for hit in &mut self.hits {
+			hit.time += dt;
+		}
+
+		self.hits.retain(|hit| hit.time < HIT_TIME);
+	}
+
+	pub fn draw(self: &BreakableDrawer, ctx: &mut Context, canvas: &mut Canvas, rules: &Rules) -> GameResult {
+		for breakable in &rules.breakables {
+			let pos = vec2(breakable.pos.0 as f32, breakable.pos.1 as f32);
+
+			// shakes and flashes while it is being hit
+			let (shake, color) = match self.hits.iter().find(|hit| hit.pos == breakable.pos && !hit.broken) {
+				Some(hit) => {
+					let fade = 1.0 - hit.time / HIT_TIME;
+					(sin(PI * hit.time * SHAKE_FREQ) * SHAKE_AMP * fade, lerp_color(kind_color(breakable.kind), HIT_COLOR, fade))
+				},
+				None => (0.0, kind_color(breakable.kind)),
+			};
+
+			self.draw_sprite(ctx, canvas, &rules.grid, pos + vec2(shake, 0.0), breakable.kind, color)?;
+			self.draw_health(ctx, canvas, &rules.grid, breakable)?;
+		}
+
+		for hit in self.hits.iter().filter(|hit| rules.grid.in_bounds(hit.pos.0, hit.pos.1)) {
+			let pos = vec2(hit.pos.0 as f32, hit.pos.1 as f32);
+			let fade = 1.0 - hit.time / HIT_TIME;
+
+			if hit.broken {
+				let mut color = kind_color(hit.kind);
+				color.a = fade;
+				self.draw_sprite(ctx, canvas, &rules.grid, pos, hit.kind, color)?;
+			}
+
+			// the damage floats up and fades
+			let mut screen_pos = Vec2::ZERO;
+			GridDrawer::grid_pos_to_screen(ctx, &rules.grid, &pos, &mut screen_pos)?;
+			screen_pos.y -= (self.sprite(hit.kind).height() as f32 * 0.75 + RISE * hit.time / HIT_TIME) * GridDrawer::field_scale(ctx, &rules.grid);
+
+			let mut text = Text::new(format!("-{}", hit.amount));
+			text.set_layout(TextLayout { h_align: TextAlign::Middle, v_align: TextAlign::End });
+
+			let mut color = HIT_COLOR;
+			color.a = fade;
+			canvas.draw(&text, DrawParam::default().dest(screen_pos).color(color).z(LABEL_Z + 1));
+		}
+
+		Ok(())
+	}
+
+	// also used by the editor, which has no Rules
+	pub fn draw_at(self: &BreakableDrawer, ctx: &mut Context, canvas: &mut Canvas, grid: &Grid, pos: Vec2, kind: BreakableKind) -> GameResult {
+		self.draw_sprite(ctx, canvas, grid, pos, kind, kind_color(kind))
+	}
+
+	fn sprite(self: &BreakableDrawer, kind: BreakableKind) -> &Image {
+		match kind {
+			BreakableKind::Dummy => &self.dummy_sprite,
+			BreakableKind::Crate => &self.crate_sprite,
+		}
+	}
+
+	// pos can be a bit off the field, e.g. while shaking
+	fn draw_sprite(self: &BreakableDrawer, ctx: &mut Context, canvas: &mut Canvas, grid: &Grid, pos: Vec2, kind: BreakableKind, color: Color) -> GameResult {
+		let field = pos.round();
+		let mut screen_pos = Vec2::ZERO;
+		GridDrawer::grid_pos_to_screen(ctx, grid, &field, &mut screen_pos)?;
+		screen_pos += (pos - field - Vec2::ONE * 0.5) * GridDrawer::field_size(ctx, grid);
+
+		GridDrawer::draw_tinted_figurine(ctx, canvas, grid, self.sprite(kind), &(screen_pos.x, screen_pos.y), color);
+
+		Ok(())
+	}
+
+	// "3/4" above it, once it took damage
+	fn draw_health(self: &BreakableDrawer, ctx: &mut Context, canvas: &mut Canvas, grid: &Grid, breakable: &Breakable) -> GameResult {
+		if breakable.health >= breakable.max_health {
+			return Ok(());
+		}
+
+		let mut screen_pos = Vec2::ZERO;
+		GridDrawer::grid_pos_to_screen(ctx, grid, &vec2(breakable.pos.0 as f32, breakable.pos.1 as f32), &mut screen_pos)?;
+		screen_pos.y -= (self.sprite(breakable.kind).height() as f32 * 0.75 + 4.0) * GridDrawer::field_scale(ctx, grid);
+
+		let mut text = Text::new(format!("{}/{}", breakable.health, breakable.max_health));
+		text.set_layout(TextLayout { h_align: TextAlign::Middle, v_align: TextAlign::End });
+
+		canvas.draw(&text, DrawParam::default().dest(screen_pos).color(kind_color(breakable.kind)).z(LABEL_Z));
+
+		Ok(())
+	}
+}
+
+fn lerp_color(from: Color, to: Color, t: f32) -> Color {
+	Color::new(lerp(&from.r, &to.r, &t), lerp(&from.g, &to.g, &t), lerp(&from.b, &to.b, &t), lerp(&from.a, &to.a, &t))
+}
@@ -0,0 +1,55 @@
+// the order of levels and how far the player got, in the pack that was picked
+
+use std::path::PathBuf;
+
+use crate::level_manager::PackLevel;
+use crate::pack::Pack;
+
+
+pub struct Campaign {
+	pub pack: Pack,
+	current: usize,
+}
+
+impl Campaign {
+	// starts at the level that was picked in the level select
+	pub fn new(pack: Pack, start: usize) -> Campaign {
+		Campaign {
+			current: start.min(pack.levels.len().saturating_sub(1)),
+			pack,
+		}
+	}
+
+	pub fn current_index(self: &Campaign) -> usize {
+		self.current
+	}
+
+	pub fn len(self: &Campaign) -> usize {
+		self.pack.levels.len()
+	}
+
+	pub fn is_empty(self: &Campaign) -> bool {
+		self.pack.levels.is_empty()
+	}
+
+	pub fn current_level(self: &Campaign) -> &PackLevel {
+		&self.pack.levels[self.current]
+	}
+
+	pub fn current_level_path(self: &Campaign) -> PathBuf {
+		self.pack.level_path(self.current)
+	}
+
+	pub fn is_last_level(self: &Campaign) -> bool {
+		self.current + 1 >= self.pack.levels.len()
+	}
+
+	// the same pack, one level further, None after the last level
+	pub fn next_level(self: &Campaign) -> Option<Campaign> {
+		if self.is_last_level() {
+			return None;
+		}
+
+		Some(Campaign::new(self.pack.clone(), self.current + 1))
+	}
+}
@@ -1,15 +1,14 @@
 use ggez::{
 	graphics::*,
-	event::{self, EventHandler},
-	conf::{Conf, Backend, WindowSetup, WindowMode, FullscreenType},
 	glam::*,
-	input::keyboard::{KeyCode, KeyMods, KeyInput},
-	Context, ContextBuilder, GameResult
+	Context, GameResult
 };
 
 use crate::global_constants as GlobConst;
 use crate::math::lerp;
 
+pub use crate::rules::{CardType, AttackCard};
+
 use std::f32::consts::PI as PI;
 
 
@@ -20,17 +19,13 @@ const ROT_SPEED: f32 = 30.0;
 const CARD_WIDTH: u32 = 31;
 const CARD_HEIGHT: u32 = 45;
 
+const COST_OFFSET: Vec2 = vec2(8.0, 6.0);		// top left corner of the card, before scaling
+const COST_COLOR: Color = Color::new(0.3, 0.7, 1.0, 1.0);
+const ATTACK_OFFSET: Vec2 = vec2(5.0, 34.0);	// bottom left corner, for Attack cards with their own shape
 
 
-#[derive(Copy, Clone)]
-pub enum CardType {
-	Move = 0,
-	Armor = 1,
-	Health = 2,
-	Key = 3
-}
-
 
+// only draws a card, the hand itself lives in Rules
 pub struct Card {
 	//transform variables
 	pos: Vec2,
@@ -51,13 +46,13 @@ pub struct Card {
 
 	// misc
 	pub card_type: CardType,
+	pub cost: Option<u32>,			// energy, only shown in levels with turns
 	drawing_shade: bool,
 }
 
 impl Card {
 	pub fn new(ctx: &mut Context, card_type: CardType) -> Card {
-		let (width, height) = ctx.gfx.drawable_size();
-		let (width_half, height_half) = (width * 1.5, height * 0.5);
+		let (_, height) = ctx.gfx.drawable_size();
 
 		let sprite = Image::from_path(ctx, "/cards.png").unwrap();
 		Card {
@@ -68,14 +63,15 @@ impl Card {
 			target_rotation: 0.0,
 
 			sprite: sprite.clone(),
-			sprite_rect: sprite.uv_rect(CARD_WIDTH *card_type as u32, 0, CARD_WIDTH, CARD_HEIGHT),
+			sprite_rect: sprite.uv_rect(CARD_WIDTH * card_type.index() as u32, 0, CARD_WIDTH, CARD_HEIGHT),
 			highlighted_border: Image::from_path(ctx, "/highlighted_border.png").unwrap(),
 			shade: Image::from_path(ctx, "/card_shade.png").unwrap(),
 
 			selected: false,
 			pos_rel_to_selected: 0.0,
 
-			card_type: card_type,
+			card_type,
+			cost: None,
 			drawing_shade: false,
 		}
 	}
@@ -83,8 +79,8 @@ impl Card {
 	pub fn update(self: &mut Card, ctx: &mut Context, amount_of_cards: u8, index: u8, dt: &f32) -> GameResult {
 		
 
-		let (width, height) = ctx.gfx.drawable_size();
-		let (width_half, height_half) = (width * 0.5, height * 0.5);
+		let (width, _) = ctx.gfx.drawable_size();
+		let width_half = width * 0.5;
 
 		let n = amount_of_cards as f32;
 		let w = CARD_WIDTH as f32;
@@ -100,7 +96,7 @@ impl Card {
 		Ok(())
 	}
 
-	pub fn draw(self: &mut Card, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+	pub fn draw(self: &mut Card, _ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
 		
 		let mut draw_param = DrawParam::default()
 			.src(self.sprite_rect)
@@ -126,6 +122,26 @@ impl Card {
 		if self.selected {
 			canvas.draw(&self.highlighted_border, draw_param);
 		}
+
+		// turns with the card
+		if let Some(cost) = self.cost {
+			let offset = Vec2::from_angle(self.rotation).rotate(COST_OFFSET * GlobConst::SCALE);
+			let mut text = Text::new(cost.to_string());
+			text.set_scale(PxScale::from(26.0));
+
+			canvas.draw(&text, DrawParam::default().dest(self.pos + offset).rotation(self.rotation).color(COST_COLOR));
+		}
+
+		// every Attack card uses the same sprite, the shape and range tell them apart
+		if let CardType::Attack(attack) = self.card_type {
+			if attack != AttackCard::MELEE {
+				let offset = Vec2::from_angle(self.rotation).rotate(ATTACK_OFFSET * GlobConst::SCALE);
+				let mut text = Text::new(attack.to_text().to_uppercase());
+				text.set_scale(PxScale::from(18.0));
+
+				canvas.draw(&text, DrawParam::default().dest(self.pos + offset).rotation(self.rotation));
+			}
+		}
 		
 		Ok(())
 	}
@@ -0,0 +1,245 @@
+use ggez::{
+	graphics::*,
+	glam::*,
+	input::keyboard::{KeyCode, KeyMods, KeyInput},
+	input::mouse::MouseButton,
+	Context, GameResult
+};
+
+use std::path::PathBuf;
+
+use crate::enemy::{Behaviour, EnemySpawn, Pattern};
+use crate::enemy_drawer::EnemyDrawer;
+use crate::breakable::{BreakableKind, BreakableSpawn};
+use crate::breakable_drawer::BreakableDrawer;
+use crate::grid_drawer as GridDrawer;
+use crate::level_manager::{self as LevelManager, Level, Tile};
+use crate::rules::{CardType, AttackCard};
+use crate::validator;
+
+
+const PANEL_X: f32 = 20.0;
+const PANEL_HEIGHT: f32 = 180.0;		// the panel sits at the bottom, where the cards are while playing
+const EXPLAIN_OPTIONS: [Option<CardType>; 4] = [None, Some(CardType::Move), Some(CardType::Key), Some(CardType::Attack(AttackCard::MELEE))];
+
+
+// a tile of the map, an enemy with the default attack and range or a breakable with the default health
+#[derive(PartialEq, Clone, Copy, Debug)]
+enum Brush {
+	Tile(Tile),
+	Enemy(Behaviour),
+	Breakable(BreakableKind),
+}
+
+// what the game has to do after a key was pressed in the editor
+pub enum EditorAction {
+	Nothing,
+	PlayTest,
+}
+
+// paints a level with the same grid drawing as the game and saves it in the same json (or text, for .txt paths) format
+pub struct Editor {
+	pub level: Level,
+	path: PathBuf,
+	brush: Brush,					// painted with the left mouse button
+	player_sprite: Image,
+	enemy_drawer: EnemyDrawer,
+	breakable_drawer: BreakableDrawer,
+	status: String,
+	problem_text: String,			// what the validator said after the last edit
+}
+
+impl Editor {
+	pub fn new(ctx: &mut Context, level: Level, path: PathBuf) -> Editor {
+		let mut editor = Editor {
+			level,
+			path,
+			brush: Brush::Tile(Tile::Tree),
+			player_sprite: Image::from_path(ctx, "/player.png").unwrap(),
+			enemy_drawer: EnemyDrawer::new(ctx),
+			breakable_drawer: BreakableDrawer::new(ctx),
+			status: String::new(),
+			problem_text: String::new(),
+		};
+		editor.validate();
+
+		editor
+	}
+
+	// the same checks as validate-level, so problems show up while painting
+	// only run after an edit, not in every frame
+	fn validate(self: &mut Editor) {
+		let problems = validator::validate_level_source(&LevelManager::level_to_json(&self.level));
+
+		self.problem_text = match problems.first() {
+			Some(problem) => format!("{} PROBLEMS, FIRST: {}", problems.len(), problem.message),
+			None => "NO PROBLEMS".to_string(),
+		};
+	}
+
+	pub fn update(self: &mut Editor, ctx: &mut Context) -> GameResult {
+		let grid = self.level.grid();
+
+		let brush = if ctx.mouse.button_pressed(MouseButton::Left) {
+			self.brush
+		} else if ctx.mouse.button_pressed(MouseButton::Right) {
+			Brush::Tile(Tile::Empty)
+		} else {
+			return Ok(());
+		};
+
+		if let Some(pos) = GridDrawer::mouse_pos_on_grid(ctx, &grid)? {
+			// holding the button paints the same field again and again
+			let before = self.level.clone();
+			self.paint(pos.x as usize, pos.y as usize, brush);
+
+			if self.level != before {
+				self.validate();
+			}
+		}
+
+		Ok(())
+	}
+
+	// tiles replace the enemy or breakable on their field, enemies and breakables only go onto empty fields
+	fn paint(self: &mut Editor, x: usize, y: usize, brush: Brush) {
+		self.level.enemies.retain(|enemy| (enemy.x, enemy.y) != (x as i32, y as i32));
+		self.level.breakables.retain(|breakable| (breakable.x, breakable.y) != (x as i32, y as i32));
+
+		let tile = match brush {
+			Brush::Tile(tile) => tile,
+			Brush::Enemy(behaviour) => {
+				// figurines strike around themselves every turn until the pattern is edited in the file
+				let pattern = if behaviour == Behaviour::Figurine { vec!(Pattern::Cross) } else { vec!() };
+
+				if self.level.map[y][x] == Tile::Empty {
+					self.level.enemies.push(EnemySpawn { behaviour, x: x as i32, y: y as i32, attack: None, range: None, pattern });
+				}
+				return;
+			},
+			Brush::Breakable(kind) => {
+				if self.level.map[y][x] == Tile::Empty {
+					self.level.breakables.push(BreakableSpawn { kind, x: x as i32, y: y as i32, health: None });
+				}
+				return;
+			},
+		};
+
+		// there is only one spawn, painting it somewhere else moves it
+		if tile == Tile::Player {
+			for row in self.level.map.iter_mut() {
+				for element in row.iter_mut() {
+					if *element == Tile::Player {
+						*element = Tile::Empty;
+					}
+				}
+			}
+		}
+
+		self.level.map[y][x] = tile;
+	}
+
+	pub fn key_down(self: &mut Editor, input: KeyInput) -> EditorAction {
+		let before = self.level.clone();
+
+		match input.keycode {
+			// 0 to 3 pick a tile, 4 to 7 an enemy and 8 and 9 a breakable, in the order the panel lists them
+			Some(KeyCode::Key0) => self.brush = Brush::Tile(Tile::Empty),
+			Some(KeyCode::Key1) => self.brush = Brush::Tile(Tile::Player),
+			Some(KeyCode::Key2) => self.brush = Brush::Tile(Tile::Tree),
+			Some(KeyCode::Key3) => self.brush = Brush::Tile(Tile::Chest),
+			Some(KeyCode::Key4) => self.brush = Brush::Enemy(Behaviour::Chaser),
+			Some(KeyCode::Key5) => self.brush = Brush::Enemy(Behaviour::Guard),
+			Some(KeyCode::Key6) => self.brush = Brush::Enemy(Behaviour::Ranged),
+			Some(KeyCode::Key7) => self.brush = Brush::Enemy(Behaviour::Figurine),
+			Some(KeyCode::Key8) => self.brush = Brush::Breakable(BreakableKind::Dummy),
+			Some(KeyCode::Key9) => self.brush = Brush::Breakable(BreakableKind::Crate),
+
+			Some(KeyCode::S) if input.mods.contains(KeyMods::CTRL) => self.save(),
+
+			Some(KeyCode::M) => self.level.cards.push(CardType::Move),
+			Some(KeyCode::K) => self.level.cards.push(CardType::Key),
+			Some(KeyCode::H) => self.level.cards.push(CardType::Health),
+			Some(KeyCode::A) => self.level.cards.push(CardType::Armor),
+			Some(KeyCode::T) => self.level.cards.push(CardType::Attack(AttackCard::MELEE)),
+			Some(KeyCode::Back) => {
+				self.level.cards.pop();
+			},
+
+			Some(KeyCode::Up) => self.level.cards_on_hand = self.level.cards_on_hand.saturating_add(1),
+			Some(KeyCode::Down) => self.level.cards_on_hand = self.level.cards_on_hand.saturating_sub(1),
+
+			Some(KeyCode::X) => {
+				let current = EXPLAIN_OPTIONS.iter()
+					.position(|option| *option == self.level.explain_on_startup)
+					.unwrap_or(0);
+				self.level.explain_on_startup = EXPLAIN_OPTIONS[(current + 1) % EXPLAIN_OPTIONS.len()];
+			},
+
+			Some(KeyCode::P) | Some(KeyCode::E) => return EditorAction::PlayTest,
+			_ => (),
+		}
+
+		if self.level != before {
+			self.validate();
+		}
+
+		EditorAction::Nothing
+	}
+
+	fn save(self: &mut Editor) {
+		self.status = match LevelManager::write_level(&self.path, &self.level) {
+			Ok(()) => format!("saved to {}", self.path.display()),
+			Err(e) => format!("couldn't save to {}: {}", self.path.display(), e),
+		};
+	}
+
+	pub fn draw(self: &mut Editor, ctx: &mut Context, canvas: &mut Canvas, tile_images: &[Image]) -> GameResult {
+		let grid = self.level.grid();
+
+		GridDrawer::draw_grid(ctx, canvas, tile_images, &grid)?;
+
+		// the spawn is drawn like a tree or a chest
+		if let Some((x, y)) = self.level.player_spawn() {
+			let mut screen_pos = Vec2::ZERO;
+			GridDrawer::grid_pos_to_screen(ctx, &grid, &vec2(x as f32, y as f32), &mut screen_pos)?;
+			screen_pos -= Vec2::ONE * GridDrawer::field_size(ctx, &grid) * 0.5;
+
+			GridDrawer::draw_figurine(ctx, canvas, &grid, &self.player_sprite, &(screen_pos.x, screen_pos.y));
+		}
+
+		for enemy in &self.level.enemies {
+			self.enemy_drawer.draw_at(ctx, canvas, &grid, vec2(enemy.x as f32, enemy.y as f32), enemy.behaviour)?;
+		}
+
+		for breakable in &self.level.breakables {
+			self.breakable_drawer.draw_at(ctx, canvas, &grid, vec2(breakable.x as f32, breakable.y as f32), breakable.kind)?;
+		}
+
+		let cards: Vec<String> = self.level.cards.iter().map(CardType::to_string).collect();
+		let explain = self.level.explain_on_startup.map_or("NOTHING".to_string(), |card| card.to_string());
+		let brush = match self.brush {
+			Brush::Tile(tile) => format!("{:?}", tile),
+			Brush::Enemy(behaviour) => format!("{:?}", behaviour),
+			Brush::Breakable(kind) => format!("{:?}", kind),
+		};
+
+		let lines = [
+			format!("EDITOR: {}", self.path.display()),
+			format!("BRUSH: {} (0 EMPTY, 1 SPAWN, 2 TREE, 3 CHEST, 4 CHASER, 5 GUARD, 6 RANGED, 7 FIGURINE, 8 DUMMY, 9 CRATE, RIGHT CLICK ERASES)", brush),
+			format!("CARDS: {} (M, K, H, A, T ADD, BACKSPACE REMOVES)", cards.join(", ")),
+			format!("CARDS ON HAND: {} (UP, DOWN)", self.level.cards_on_hand),
+			format!("EXPLAIN ON STARTUP: {} (X)", explain),
+			"P PLAY-TEST, CTRL+S SAVE".to_string(),
+			self.problem_text.clone(),
+			self.status.clone(),
+		];
+
+		let (_, screen_h) = ctx.gfx.drawable_size();
+
+		let text = Text::new(lines.join("\n"));
+		canvas.draw(&text, DrawParam::default().dest(vec2(PANEL_X, screen_h - PANEL_HEIGHT)).z(2000));
+
+		Ok(())
+	}
+}
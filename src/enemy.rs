@@ -0,0 +1,361 @@
+// enemies on the grid: where a level places them and what they do in the enemy turn after every player action
+// headless like Rules, EnemyDrawer only draws them
+
+use serde::{Serialize, Deserialize};
+
+use crate::rules::{Rules, State};
+
+
+pub const DEFAULT_ATTACK: f32 = 1.0;
+
+// tried in this order, so ties are always broken the same way and replays stay deterministic
+const STEPS: [(i32, i32); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum Behaviour {
+	Chaser,			// walks toward the player and attacks when standing next to them
+	Guard,			// only leaves its post for players within range of it
+	Ranged,			// shoots along rows and columns, walks to line up with the player
+	Figurine,		// never moves, strikes its pattern one after the other
+}
+
+impl Behaviour {
+	// used if the level doesn't give a range
+	pub fn default_range(self: Behaviour) -> u32 {
+		match self {
+			Behaviour::Chaser => 1,
+			Behaviour::Guard => 2,
+			Behaviour::Ranged => 3,
+			Behaviour::Figurine => 2,
+		}
+	}
+
+	// text levels and Tiled maps, case doesn't matter
+	pub fn from_name(name: &str) -> Option<Behaviour> {
+		match name.trim().to_lowercase().as_str() {
+			"chaser" => Some(Behaviour::Chaser),
+			"guard" => Some(Behaviour::Guard),
+			"ranged" => Some(Behaviour::Ranged),
+			"figurine" => Some(Behaviour::Figurine),
+			_ => None,
+		}
+	}
+}
+
+// the fields a figurine strikes in one turn, around itself
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum Pattern {
+	Wait,
+	Cross,			// the four fields next to it
+	Diagonals,		// the four fields touching its corners
+	Ring,			// all eight fields around it
+	Row,			// left and right, as far as the range
+	Column,			// up and down, as far as the range
+}
+
+impl Pattern {
+	pub fn from_name(name: &str) -> Option<Pattern> {
+		match name.trim().to_lowercase().as_str() {
+			"wait" => Some(Pattern::Wait),
+			"cross" => Some(Pattern::Cross),
+			"diagonals" => Some(Pattern::Diagonals),
+			"ring" => Some(Pattern::Ring),
+			"row" => Some(Pattern::Row),
+			"column" => Some(Pattern::Column),
+			_ => None,
+		}
+	}
+
+	// fields outside of the grid are left for the caller to skip
+	pub fn fields(self: Pattern, pos: (i32, i32), range: u32) -> Vec<(i32, i32)> {
+		let range = range as i32;
+		let offsets: Vec<(i32, i32)> = match self {
+			Pattern::Wait => vec!(),
+			Pattern::Cross => STEPS.to_vec(),
+			Pattern::Diagonals => vec!((-1, -1), (1, -1), (-1, 1), (1, 1)),
+			Pattern::Ring => vec!((-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)),
+			Pattern::Row => (1..=range).flat_map(|d| [(-d, 0), (d, 0)]).collect(),
+			Pattern::Column => (1..=range).flat_map(|d| [(0, -d), (0, d)]).collect(),
+		};
+
+		offsets.into_iter().map(|(dx, dy)| (pos.0 + dx, pos.1 + dy)).collect()
+	}
+}
+
+// an enemy as the level file places it, attack and range fall back to the defaults
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct EnemySpawn {
+	pub behaviour: Behaviour,
+	pub x: i32,
+	pub y: i32,
+	#[serde(default)]
+	pub attack: Option<f32>,
+	#[serde(default)]
+	pub range: Option<u32>,
+	#[serde(default)]
+	pub pattern: Vec<Pattern>,		// only figurines have one, it starts over after the last entry
+}
+
+impl EnemySpawn {
+	pub fn enemy(self: &EnemySpawn) -> Enemy {
+		Enemy {
+			behaviour: self.behaviour,
+			pos: (self.x, self.y),
+			home: (self.x, self.y),
+			attack: self.attack.unwrap_or(DEFAULT_ATTACK),
+			range: self.range.unwrap_or_else(|| self.behaviour.default_range()),
+			pattern: self.pattern.clone(),
+			turn: 0,
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+pub struct Enemy {
+	pub behaviour: Behaviour,
+	pub pos: (i32, i32),
+	pub home: (i32, i32),			// where it was placed, guards stay close to it
+	pub attack: f32,				// damage of every hit
+	pub range: u32,					// guard radius, shooting distance or length of a row or column pattern
+	#[serde(default)]
+	pub pattern: Vec<Pattern>,
+	#[serde(default)]
+	pub turn: usize,				// enemy turns so far, picks the entry of the pattern
+}
+
+// what an enemy does in its turn
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Intent {
+	Wait,
+	Move((i32, i32)),
+	Attack((i32, i32)),
+	Strike(Pattern),				// hits whoever stands in the pattern
+}
+
+fn distance(a: (i32, i32), b: (i32, i32)) -> i32 {
+	(a.0 - b.0).abs() + (a.1 - b.1).abs()
+}
+
+// fields enemies can walk onto: empty, without the player, another enemy or a breakable
+fn is_free(rules: &Rules, pos: (i32, i32)) -> bool {
+	rules.grid.get(pos.0, pos.1) == Some(State::Empty) && pos != rules.player_pos && !rules.enemy_at(pos) && !rules.breakable_at(pos)
+}
+
+impl Enemy {
+	// decided on the state right before the enemy acts
+	pub fn intent(self: &Enemy, rules: &Rules) -> Intent {
+		let player = rules.player_pos;
+
+		match self.behaviour {
+			Behaviour::Chaser => {
+				if distance(self.pos, player) <= 1 {
+					return Intent::Attack(player);
+				}
+
+				self.step(rules, |pos| distance(pos, player))
+			},
+			Behaviour::Guard => {
+				if distance(self.pos, player) <= 1 {
+					return Intent::Attack(player);
+				}
+
+				// players close to the post are chased, but never further than the range
+				let range = self.range as i32;
+				if distance(self.home, player) <= range {
+					self.step(rules, |pos| if distance(self.home, pos) <= range { distance(pos, player) } else { i32::MAX })
+				} else {
+					self.step(rules, |pos| distance(pos, self.home))
+				}
+			},
+			Behaviour::Ranged => {
+				if self.can_shoot(rules, player) {
+					return Intent::Attack(player);
+				}
+
+				// getting into the same row or column matters more than getting close
+				self.step(rules, |pos| (pos.0 - player.0).abs().min((pos.1 - player.1).abs()) * 1000 + distance(pos, player))
+			},
+			Behaviour::Figurine => Intent::Strike(self.next_pattern()),
+		}
+	}
+
+	pub fn next_pattern(self: &Enemy) -> Pattern {
+		if self.pattern.is_empty() {
+			return Pattern::Wait;
+		}
+
+		self.pattern[self.turn % self.pattern.len()]
+	}
+
+	// the fields the enemy would attack in its next turn if the player stood there
+	pub fn threatened(self: &Enemy, rules: &Rules) -> Vec<(i32, i32)> {
+		let fields = match self.behaviour {
+			Behaviour::Chaser | Behaviour::Guard => Pattern::Cross.fields(self.pos, 1),
+			Behaviour::Ranged => Pattern::Row.fields(self.pos, self.range).into_iter()
+				.chain(Pattern::Column.fields(self.pos, self.range))
+				.filter(|pos| self.can_shoot(rules, *pos))
+				.collect(),
+			Behaviour::Figurine => self.next_pattern().fields(self.pos, self.range),
+		};
+
+		fields.into_iter().filter(|(x, y)| rules.grid.in_bounds(*x, *y)).collect()
+	}
+
+	// one step onto a free field with a lower score than the current one, waits if there is none
+	fn step<F: Fn((i32, i32)) -> i32>(self: &Enemy, rules: &Rules, score: F) -> Intent {
+		let mut best = (score(self.pos), None);
+
+		for (dx, dy) in STEPS {
+			let pos = (self.pos.0 + dx, self.pos.1 + dy);
+			if !is_free(rules, pos) {
+				continue;
+			}
+
+			let s = score(pos);
+			if s < best.0 {
+				best = (s, Some(pos));
+			}
+		}
+
+		best.1.map_or(Intent::Wait, Intent::Move)
+	}
+
+	// same row or column, in range and nothing in between
+	fn can_shoot(self: &Enemy, rules: &Rules, target: (i32, i32)) -> bool {
+		let (dx, dy) = (target.0 - self.pos.0, target.1 - self.pos.1);
+
+		if (dx != 0 && dy != 0) || distance(self.pos, target) > self.range as i32 {
+			return false;
+		}
+
+		let (sx, sy) = (dx.signum(), dy.signum());
+		let mut pos = (self.pos.0 + sx, self.pos.1 + sy);
+
+		while pos != target {
+			if rules.grid.get(pos.0, pos.1) != Some(State::Empty) || rules.enemy_at(pos) || rules.breakable_at(pos) {
+				return false;
+			}
+			pos = (pos.0 + sx, pos.1 + sy);
+		}
+
+		true
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::rules::{Grid, ValueType};
+
+	fn spawn(behaviour: Behaviour, pos: (i32, i32)) -> Enemy {
+		EnemySpawn { behaviour, x: pos.0, y: pos.1, attack: None, range: None, pattern: vec!() }.enemy()
+	}
+
+	// the player in the top left corner of an empty grid
+	fn rules_with(enemies: Vec<Enemy>) -> Rules {
+		let mut rules = Rules::new();
+		rules.grid = Grid::new(7, 7);
+		rules.enemies = enemies;
+
+		rules
+	}
+
+	#[test]
+	fn chasers_walk_up_and_attack_next_to_the_player() {
+		let mut rules = rules_with(vec!(spawn(Behaviour::Chaser, (0, 3))));
+
+		assert_eq!(rules.enemies[0].intent(&rules), Intent::Move((0, 2)));
+		rules.enemy_turn();
+		rules.enemy_turn();
+		assert_eq!(rules.enemies[0].pos, (0, 1));
+		assert_eq!(rules.enemies[0].intent(&rules), Intent::Attack((0, 0)));
+	}
+
+	#[test]
+	fn an_enemy_attack_damages_the_player() {
+		let mut rules = rules_with(vec!(spawn(Behaviour::Chaser, (1, 0))));
+		let armor = rules.value(ValueType::Armor);
+
+		rules.enemy_turn();
+		assert_eq!(rules.value(ValueType::Armor), armor - DEFAULT_ATTACK);
+		assert_eq!(rules.enemies[0].pos, (1, 0));
+	}
+
+	#[test]
+	fn guards_stay_close_to_their_post() {
+		let mut rules = rules_with(vec!(spawn(Behaviour::Guard, (4, 4))));
+		assert_eq!(rules.enemies[0].intent(&rules), Intent::Wait);
+
+		// a player near the post is chased, one that walked away again makes it go back
+		rules.player_pos = (4, 2);
+		assert_eq!(rules.enemies[0].intent(&rules), Intent::Move((4, 3)));
+
+		rules.enemies[0].pos = (4, 3);
+		rules.player_pos = (0, 0);
+		assert_eq!(rules.enemies[0].intent(&rules), Intent::Move((4, 4)));
+	}
+
+	#[test]
+	fn ranged_enemies_need_a_free_line() {
+		let mut rules = rules_with(vec!(spawn(Behaviour::Ranged, (3, 0))));
+		assert_eq!(rules.enemies[0].intent(&rules), Intent::Attack((0, 0)));
+
+		rules.grid.set(1, 0, State::Tree);
+		assert_ne!(rules.enemies[0].intent(&rules), Intent::Attack((0, 0)));
+
+		// out of line it walks to get into the player's row or column
+		let rules = rules_with(vec!(spawn(Behaviour::Ranged, (2, 2))));
+		assert_eq!(rules.enemies[0].intent(&rules), Intent::Move((2, 1)));
+	}
+
+	#[test]
+	fn figurines_strike_their_pattern_in_turn() {
+		let mut figurine = spawn(Behaviour::Figurine, (1, 1));
+		figurine.pattern = vec!(Pattern::Cross, Pattern::Wait);
+		let mut rules = rules_with(vec!(figurine));
+		rules.player_pos = (2, 2);
+
+		// the diagonal is safe from the cross, waiting hurts nobody either
+		let health = rules.value(ValueType::Armor) + rules.value(ValueType::Health);
+		rules.enemy_turn();
+		rules.enemy_turn();
+		assert_eq!(rules.value(ValueType::Armor) + rules.value(ValueType::Health), health);
+
+		rules.player_pos = (1, 2);
+		assert_eq!(rules.enemies[0].intent(&rules), Intent::Strike(Pattern::Cross));
+		rules.enemy_turn();
+		assert_eq!(rules.value(ValueType::Armor) + rules.value(ValueType::Health), health - DEFAULT_ATTACK);
+		assert_eq!(rules.enemies[0].pos, (1, 1));
+	}
+
+	#[test]
+	fn the_danger_zone_shows_the_next_strikes_on_the_grid() {
+		let mut figurine = spawn(Behaviour::Figurine, (0, 6));
+		figurine.pattern = vec!(Pattern::Row, Pattern::Ring);
+		let mut rules = rules_with(vec!(figurine));
+
+		// the range of 2 reaches two fields, the left side is off the grid
+		assert_eq!(rules.danger_zone(), vec!((1, 6), (2, 6)));
+
+		rules.enemies[0].turn += 1;
+		assert_eq!(rules.danger_zone(), vec!((0, 5), (1, 5), (1, 6)));
+
+		// fields of several enemies are only listed once
+		rules.enemies.push(spawn(Behaviour::Chaser, (1, 4)));
+		assert_eq!(rules.danger_zone(), vec!((0, 4), (0, 5), (1, 3), (1, 5), (1, 6), (2, 4)));
+	}
+
+	#[test]
+	fn blocked_enemies_wait() {
+		let mut rules = rules_with(vec!(spawn(Behaviour::Chaser, (0, 2)), spawn(Behaviour::Chaser, (0, 3))));
+		assert_eq!(rules.enemies[1].intent(&rules), Intent::Wait);
+
+		// each one acts on the state the ones before it left behind
+		rules.enemy_turn();
+		assert_eq!(rules.enemies[0].pos, (0, 1));
+		assert_eq!(rules.enemies[1].pos, (0, 2));
+	}
+}
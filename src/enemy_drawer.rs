@@ -0,0 +1,118 @@
+// draws the enemies in Rules as figurines that walk from field to field like the player,
+// with what each of them does next written above it
+
+use ggez::{
+	graphics::*,
+	glam::*,
+	Context, GameResult
+};
+
+use crate::enemy::{Behaviour, Enemy, Intent, Pattern};
+use crate::grid::Grid;
+use crate::grid_drawer as GridDrawer;
+use crate::rules::Rules;
+
+
+const TRAVEL_SPEED: f32 = 10.0;
+const INTENT_Z: i32 = 1500;			// above every figurine
+
+
+// enemies use the player sprite, every behaviour in its own color
+pub fn behaviour_color(behaviour: Behaviour) -> Color {
+	match behaviour {
+		Behaviour::Chaser => Color::from_rgb(200, 60, 40),
+		Behaviour::Guard => Color::from_rgb(220, 160, 40),
+		Behaviour::Ranged => Color::from_rgb(150, 80, 200),
+		Behaviour::Figurine => Color::from_rgb(140, 140, 150),
+	}
+}
+
+pub struct EnemyDrawer {
+	sprite: Image,
+	positions: Vec<Vec2>,		// animated positions in the grid, one per enemy in Rules
+}
+
+impl EnemyDrawer {
+	pub fn new(ctx: &mut Context) -> EnemyDrawer {
+		EnemyDrawer {
+			sprite: Image::from_path(ctx, "/player.png").unwrap(),
+			positions: vec!(),
+		}
+	}
+
+	// enemies only ever walk one field, anything further is a new level or a restart and jumps
+	pub fn update(self: &mut EnemyDrawer, dt: f32, rules: &Rules) {
+		let targets: Vec<Vec2> = rules.enemies.iter().map(|enemy| vec2(enemy.pos.0 as f32, enemy.pos.1 as f32)).collect();
+
+		let jumped = self.positions.len() != targets.len() || self.positions.iter().zip(&targets).any(|(pos, target)| pos.distance(*target) > 1.5);
+		if jumped {
+			self.positions = targets;
+			return;
+		}
+
+		for (pos, target) in self.positions.iter_mut().zip(targets) {
+			*pos = pos.lerp(target, TRAVEL_SPEED * dt);
+
+			if pos.distance(target) <= 0.05 {
+				*pos = target;
+			}
+		}
+	}
+
+	// e.g. when a level starts, the old positions don't belong to the new enemies
+	pub fn clear(self: &mut EnemyDrawer) {
+		self.positions.clear();
+	}
+
+	// update() doesn't run while the screen fades, until then the enemies are drawn on their fields
+	pub fn draw(self: &EnemyDrawer, ctx: &mut Context, canvas: &mut Canvas, rules: &Rules) -> GameResult {
+		let animated = self.positions.len() == rules.enemies.len()
+			&& self.positions.iter().all(|pos| pos.x >= 0.0 && pos.y >= 0.0 && pos.x < rules.grid.width() as f32 && pos.y < rules.grid.height() as f32);
+
+		for (i, enemy) in rules.enemies.iter().enumerate() {
+			let pos = if animated { self.positions[i] } else { vec2(enemy.pos.0 as f32, enemy.pos.1 as f32) };
+			self.draw_at(ctx, canvas, &rules.grid, pos, enemy.behaviour)?;
+
+			// the intent as it is right now, it can still change with the player's next action
+			let mut screen_pos = Vec2::ZERO;
+			GridDrawer::grid_pos_to_screen(ctx, &rules.grid, &pos, &mut screen_pos)?;
+			screen_pos.y -= (self.sprite.height() as f32 * 0.75 + 4.0) * GridDrawer::field_scale(ctx, &rules.grid);
+
+			let mut text = Text::new(intent_label(enemy, enemy.intent(rules)));
+			text.set_layout(TextLayout { h_align: TextAlign::Middle, v_align: TextAlign::End });
+
+			canvas.draw(&text, DrawParam::default().dest(screen_pos).color(behaviour_color(enemy.behaviour)).z(INTENT_Z));
+		}
+
+		Ok(())
+	}
+
+	// also used by the editor, which has no Rules
+	pub fn draw_at(self: &EnemyDrawer, ctx: &mut Context, canvas: &mut Canvas, grid: &Grid, pos: Vec2, behaviour: Behaviour) -> GameResult {
+		let mut screen_pos = Vec2::ZERO;
+		GridDrawer::grid_pos_to_screen(ctx, grid, &pos, &mut screen_pos)?;
+		screen_pos -= Vec2::ONE * GridDrawer::field_size(ctx, grid) * 0.5;
+
+		GridDrawer::draw_tinted_figurine(ctx, canvas, grid, &self.sprite, &(screen_pos.x, screen_pos.y), behaviour_color(behaviour));
+
+		Ok(())
+	}
+}
+
+// "!2" for an attack with 2 damage, an arrow for a step, the pattern for figurines
+fn intent_label(enemy: &Enemy, intent: Intent) -> String {
+	match intent {
+		Intent::Wait | Intent::Strike(Pattern::Wait) => "...".to_string(),
+		Intent::Move((x, y)) => {
+			let arrow = match (x - enemy.pos.0, y - enemy.pos.1) {
+				(dx, _) if dx < 0 => "<",
+				(dx, _) if dx > 0 => ">",
+				(_, dy) if dy < 0 => "^",
+				_ => "v",
+			};
+			arrow.to_string()
+		},
+		Intent::Attack(_) => format!("!{}", enemy.attack),
+		Intent::Strike(pattern) => format!("{:?} !{}", pattern, enemy.attack).to_uppercase(),
+	}
+}
@@ -1,19 +1,14 @@
 use ggez::{
 	graphics::*,
-	event::{self, EventHandler},
-	conf::{Conf, Backend, WindowSetup, WindowMode, FullscreenType},
 	glam::*,
-	mint::Point2,
-	input::keyboard::{KeyCode, KeyMods, KeyInput},
-	input::mouse::{MouseContext, MouseButton},
-	Context, ContextBuilder, GameResult, GameError
+	Context, GameResult
 };
 
 use crate::card::*;
 use crate::global_constants as GlobConst;
 
 
-const DIFF_CARD_TYPES: usize = 4;
+const DIFF_CARD_TYPES: usize = 5;
 const POSITION: Vec2 = vec2(1350.0, 100.0);
 
 
@@ -36,12 +31,13 @@ impl Explainer {
 			drawing: false,
 
 			background_mesh: Mesh::new_rectangle(ctx, DrawMode::fill(), Rect::new(0.0, 0.0, 400.0, 480.0), Color::new(0.0, 0.0, 0.0, 0.4)).unwrap(),
-			title_images: [Image::from_path(ctx, "/titles/key_title.png").unwrap(), Image::from_path(ctx, "/titles/key_title.png").unwrap(), Image::from_path(ctx, "/titles/key_title.png").unwrap(), Image::from_path(ctx, "/titles/key_title.png").unwrap()],
+			title_images: [Image::from_path(ctx, "/titles/key_title.png").unwrap(), Image::from_path(ctx, "/titles/key_title.png").unwrap(), Image::from_path(ctx, "/titles/key_title.png").unwrap(), Image::from_path(ctx, "/titles/key_title.png").unwrap(), Image::from_path(ctx, "/titles/attack_title.png").unwrap()],
 			explanations: [
 				Text::new("The MOVE card moves you to another field in your range. Use it to get closer to a chest."),
 				Text::new("The KEY card opens any chest touching the player"),
 				Text::new("The KEY card opens any chest touching the player"),
 				Text::new("The KEY card opens any chest touching the\nplayer. You will advance to the next level."),
+				Text::new("The ATTACK card hits dummies and crates\nwith your attack value. Broken ones clear\nthe way. Enemies can't be hurt.\nSome hit a whole line or area."),
 			],
 			explain_idx: 0,
 			close_text: Text::new("PRESS 'C' TO CLOSE"),
@@ -49,17 +45,33 @@ impl Explainer {
 	}
 
 	pub fn explain(self: &mut Explainer, card_type: CardType) {
-		if self.explained[card_type as usize] {
+		if self.explained[card_type.index()] {
 			return;
 		}
 
-		self.explained[card_type as usize] = true;
+		self.explained[card_type.index()] = true;
 
-		self.explain_idx = card_type as usize;
+		self.explain_idx = card_type.index();
 		self.drawing = true;
 	}
 
-	pub fn draw(self: &mut Explainer, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+	pub fn close(self: &mut Explainer) {
+		self.drawing = false;
+	}
+
+	// the tutorials that were already shown, kept in the save game
+	pub fn explained(self: &Explainer) -> Vec<CardType> {
+		[CardType::Move, CardType::Armor, CardType::Health, CardType::Key, CardType::Attack(AttackCard::MELEE)].into_iter()
+			.filter(|card_type| self.explained[card_type.index()])
+			.collect()
+	}
+
+	// marks a tutorial as seen without showing it
+	pub fn mark_explained(self: &mut Explainer, card_type: CardType) {
+		self.explained[card_type.index()] = true;
+	}
+
+	pub fn draw(self: &mut Explainer, _ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
 		
 		if !self.drawing {
 			return Ok(());
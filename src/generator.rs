@@ -0,0 +1,174 @@
+// random levels that the solver has proven to be winnable
+
+use rand::{Rng, SeedableRng, seq::SliceRandom};
+use rand_chacha::ChaCha8Rng;
+
+use std::collections::BTreeMap;
+
+use crate::level_manager::{Level, Tile};
+use crate::rules::CardType;
+use crate::solver::{self, Report};
+
+
+const MAX_ATTEMPTS: usize = 2000;
+
+pub const MAX_CARDS: usize = u8::MAX as usize;		// levels store the hand size as a u8
+
+
+pub struct Settings {
+	pub width: usize,
+	pub height: usize,
+	pub difficulty: f32,		// 0.0 (easy) to 1.0 (hard)
+	pub max_cards: usize,		// the deck never has more cards than this, at most MAX_CARDS
+	pub seed: u64,
+}
+
+pub struct Generated {
+	pub level: Level,
+	pub report: Report,			// the solver's report on the final level
+	pub attempts: usize,
+}
+
+// the same settings always generate the same level
+// attempts are only checked for a solution, counting all of them is left for the level that is kept
+pub fn generate(settings: &Settings) -> Option<Generated> {
+	let mut rng = ChaCha8Rng::seed_from_u64(settings.seed);
+
+	for attempt in 1..=MAX_ATTEMPTS {
+		if let Some(level) = try_generate(settings, &mut rng) {
+			let report = solver::solve(&level.rules(0));
+			return Some(Generated { level, report, attempts: attempt });
+		}
+	}
+
+	None
+}
+
+fn try_generate(settings: &Settings, rng: &mut ChaCha8Rng) -> Option<Level> {
+	let (width, height) = (settings.width, settings.height);
+	let difficulty = settings.difficulty.clamp(0.0, 1.0);
+	let max_cards = settings.max_cards.min(MAX_CARDS);
+
+	if width * height < 2 || max_cards < 2 {
+		return None;
+	}
+
+	let mut map = vec![vec![Tile::Empty; width]; height];
+
+	// harder levels put the chest further away from the spawn
+	let (px, py) = (rng.gen_range(0, width), rng.gen_range(0, height));
+	let (cx, cy) = (rng.gen_range(0, width), rng.gen_range(0, height));
+	let distance = px.abs_diff(cx) + py.abs_diff(cy);
+	let min_distance = 1 + (difficulty * (width + height) as f32 * 0.5) as usize;
+
+	if distance < min_distance.min(width + height - 2).max(1) {
+		return None;
+	}
+
+	map[py][px] = Tile::Player;
+	map[cy][cx] = Tile::Chest;
+
+	// and grow more trees
+	let tree_chance = 0.05 + 0.25 * difficulty;
+	for row in map.iter_mut() {
+		for tile in row.iter_mut() {
+			if *tile == Tile::Empty && rng.gen::<f32>() < tree_chance {
+				*tile = Tile::Tree;
+			}
+		}
+	}
+
+	// first find out how many cards the shortest way needs, with moves to spare
+	let mut level = Level {
+		width,
+		height,
+		map,
+		cards: vec!(),
+		cards_on_hand: max_cards as u8,
+		explain_on_startup: None,
+		shuffle: false,
+		seed: None,
+		undo_limit: None,
+		enemies: vec!(),
+		energy_per_turn: None,
+		card_costs: BTreeMap::new(),
+		turn_limit: None,
+		breakables: vec!(),
+	};
+	level.cards = vec![CardType::Move; max_cards - 1];
+	level.cards.push(CardType::Key);
+
+	let needed = solver::card_plays(&solver::find_shortest(&level.rules(0))?);
+	if needed > max_cards {
+		return None;
+	}
+
+	// the cards that are needed plus some spares, easy levels get spare moves and hard ones useless cards
+	let spare_count = rng.gen_range(0, max_cards - needed + 1);
+	let mut deck = vec![CardType::Move; needed - 1];
+	deck.push(CardType::Key);
+
+	for _ in 0..spare_count {
+		let spare = if rng.gen::<f32>() >= difficulty {
+			CardType::Move
+		} else if rng.gen::<bool>() {
+			CardType::Health
+		} else {
+			CardType::Armor
+		};
+		deck.push(spare);
+	}
+
+	// the file order is the draw order, a small hand makes it matter
+	deck.shuffle(rng);
+	let hand = ((deck.len() as f32) * (1.0 - 0.6 * difficulty)).ceil().max(1.0) as usize;
+
+	level.cards = deck;
+	level.cards_on_hand = hand as u8;
+
+	solver::find_shortest(&level.rules(0))?;
+
+	Some(level)
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::level_manager::level_to_json;
+
+	fn settings(max_cards: usize, seed: u64) -> Settings {
+		Settings { width: 5, height: 4, difficulty: 0.5, max_cards, seed }
+	}
+
+	#[test]
+	fn the_same_seed_generates_the_same_level() {
+		let first = generate(&settings(8, 7)).unwrap();
+		let second = generate(&settings(8, 7)).unwrap();
+
+		assert_eq!(level_to_json(&first.level), level_to_json(&second.level));
+		assert_eq!(first.attempts, second.attempts);
+	}
+
+	#[test]
+	fn generated_levels_are_solvable() {
+		for seed in 0..10 {
+			let generated = generate(&settings(6, seed)).unwrap();
+			let report = &generated.report;
+
+			assert!(report.solvable());
+			assert!(report.solutions > 0);
+			assert!(generated.level.cards.len() <= 6);
+			assert!(report.card_plays().unwrap() <= generated.level.cards.len());
+		}
+	}
+
+	#[test]
+	fn too_many_cards_are_clamped() {
+		let generated = generate(&Settings { width: 3, height: 3, difficulty: 0.0, max_cards: 1000, seed: 1 }).unwrap();
+
+		assert!(generated.level.cards.len() <= MAX_CARDS);
+		assert!(generated.level.cards_on_hand as usize <= generated.level.cards.len());
+		assert!(generated.level.cards_on_hand > 0);
+	}
+}
@@ -0,0 +1,116 @@
+// an owned grid of tiles, every level (or solver, or test) has its own
+
+use serde::{Serialize, Deserialize};
+
+use std::fmt;
+
+
+// size of a grid if nothing else is known
+pub const DEFAULT_GRID_SIZE: usize = 7;
+
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum State {
+	Empty = 0,
+	Tree = 1,
+	Chest = 2,
+}
+
+impl State {
+	// the same characters as text levels
+	pub fn to_char(self: State) -> char {
+		match self {
+			State::Empty => '.',
+			State::Tree => 'T',
+			State::Chest => 'C',
+		}
+	}
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Debug)]
+pub struct Grid {
+	width: usize,
+	height: usize,
+	tiles: Vec<State>,		// row by row, indexed y * width + x
+}
+
+impl Default for Grid {
+	fn default() -> Self {
+		Grid::new(DEFAULT_GRID_SIZE, DEFAULT_GRID_SIZE)
+	}
+}
+
+impl Grid {
+	pub fn new(width: usize, height: usize) -> Grid {
+		Grid {
+			width,
+			height,
+			tiles: vec![State::Empty; width * height],
+		}
+	}
+
+	pub fn width(self: &Grid) -> usize {
+		self.width
+	}
+
+	pub fn height(self: &Grid) -> usize {
+		self.height
+	}
+
+	// a grid read from a file could have the wrong number of tiles
+	pub fn is_consistent(self: &Grid) -> bool {
+		self.tiles.len() == self.width * self.height
+	}
+
+	pub fn in_bounds(self: &Grid, x: i32, y: i32) -> bool {
+		x >= 0 && x < self.width() as i32 && y >= 0 && y < self.height() as i32
+	}
+
+	// None if (x, y) is outside of the grid
+	pub fn get(self: &Grid, x: i32, y: i32) -> Option<State> {
+		if !self.in_bounds(x, y) {
+			return None;
+		}
+
+		Some(self.tiles[y as usize * self.width + x as usize])
+	}
+
+	pub fn set(self: &mut Grid, x: i32, y: i32, state: State) {
+		assert!(self.in_bounds(x, y), "({}, {}) is outside of the grid", x, y);
+
+		self.tiles[y as usize * self.width + x as usize] = state;
+	}
+
+	pub fn clear(self: &mut Grid) {
+		self.tiles.fill(State::Empty);
+	}
+
+	// every tile as (x, y, state), row by row
+	pub fn iter(self: &Grid) -> impl Iterator<Item = (i32, i32, State)> + '_ {
+		self.tiles.iter().enumerate().map(|(i, state)| ((i % self.width) as i32, (i / self.width) as i32, *state))
+	}
+
+	// the position offset away from (x, y), None if that's outside of the grid
+	pub fn neighbor(self: &Grid, x: i32, y: i32, offset: (i32, i32)) -> Option<(i32, i32)> {
+		let (nx, ny) = (x + offset.0, y + offset.1);
+
+		if self.in_bounds(nx, ny) { Some((nx, ny)) } else { None }
+	}
+
+	// all offsets that stay inside of the grid
+	pub fn neighbors<'a>(self: &'a Grid, x: i32, y: i32, offsets: &'a [(i32, i32)]) -> impl Iterator<Item = (i32, i32)> + 'a {
+		offsets.iter().filter_map(move |offset| self.neighbor(x, y, *offset))
+	}
+}
+
+// one line per row, e.g. for logs and bug reports
+impl fmt::Display for Grid {
+	fn fmt(self: &Grid, f: &mut fmt::Formatter) -> fmt::Result {
+		for y in 0..self.height as i32 {
+			let row: String = (0..self.width as i32).map(|x| self.get(x, y).unwrap().to_char()).collect();
+			writeln!(f, "{}", row)?;
+		}
+
+		Ok(())
+	}
+}
@@ -1,130 +1,140 @@
 use ggez::{
 	graphics::*,
 	glam::*,
-	Context, GameResult, GameError
+	Context, GameResult
 };
 
-use std::sync::{OnceLock, Mutex};
-
 use crate::global_constants as GlobConst;
-use crate::player::*;
+use crate::grid::Grid;
+
+pub use crate::grid::State;
 
 
 //consts
-const TILE_FIELD_SIZE: f32 = 175.0;
-const TILE_FIELD_Y: f32 = 25.0;
-pub const TILES_PER_ROW: f32 = 7.0;				// pub bc is used by the player
-pub const TILE_SIZE: f32 = TILE_FIELD_SIZE / TILES_PER_ROW;
+pub const TILE_SIZE: f32 = 25.0;				// a tile in pixels, before scaling
+
+// the free area around the tile field, before scaling
+const FIELD_MARGIN_TOP: f32 = 25.0;
+const FIELD_MARGIN_BOTTOM: f32 = 40.0;			// the hand of cards
+const FIELD_MARGIN_SIDE: f32 = 25.0;			// the value bars, on both sides to stay centered
+
+
+// what the tile field and everything on it is scaled by, big grids get smaller tiles so they fit the free area
+// small grids are never drawn bigger than GlobConst::SCALE
+pub fn field_scale(ctx: &Context, grid: &Grid) -> f32 {
+	let (width, height) = ctx.gfx.drawable_size();
+
+	let free_width = width - FIELD_MARGIN_SIDE * 2.0 * GlobConst::SCALE;
+	let free_height = height - (FIELD_MARGIN_TOP + FIELD_MARGIN_BOTTOM) * GlobConst::SCALE;
 
+	let fit = (free_width / grid.width().max(1) as f32).min(free_height / grid.height().max(1) as f32) / TILE_SIZE;
 
-#[derive(PartialEq, Clone, Copy)]
-pub enum State {
-	Empty = 0,
-	Tree = 1,
-	Chest = 2,
+	fit.clamp(0.1, GlobConst::SCALE)
 }
 
-pub fn states() -> &'static Mutex<[[State; TILES_PER_ROW as usize]; TILES_PER_ROW as usize]> {
-	static STATES: OnceLock<Mutex<[[State; TILES_PER_ROW as usize]; TILES_PER_ROW as usize]>> = OnceLock::new();
-	STATES.get_or_init(|| Mutex::new([[State::Empty; TILES_PER_ROW as usize]; TILES_PER_ROW as usize]))
+// the size of one field on the screen
+pub fn field_size(ctx: &Context, grid: &Grid) -> f32 {
+	TILE_SIZE * field_scale(ctx, grid)
 }
 
+// top left corner of the tile field, which is centered in the free area
+fn field_origin(ctx: &Context, grid: &Grid) -> Vec2 {
+	let (width, height) = ctx.gfx.drawable_size();
+	let size = vec2(grid.width() as f32, grid.height() as f32) * field_size(ctx, grid);
 
-pub fn set_state_at(x: usize, y: usize, state: State) {
-	states().lock().unwrap()[x][y] = state;
+	let top = FIELD_MARGIN_TOP * GlobConst::SCALE;
+	let free_height = height - (FIELD_MARGIN_TOP + FIELD_MARGIN_BOTTOM) * GlobConst::SCALE;
+
+	vec2(
+		(width - size.x) * 0.5,
+		top + ((free_height - size.y) * 0.5).max(0.0)
+	)
 }
 
 
-pub fn draw_grid(ctx: &mut Context, canvas: &mut Canvas, images: &Vec<Image>) -> GameResult {
+pub fn draw_grid(ctx: &mut Context, canvas: &mut Canvas, images: &[Image], grid: &Grid) -> GameResult {
 	
-	let (width, height) = ctx.gfx.drawable_size();
-	let (width_half, height_half) = (width * 0.5, height * 0.5);
+	let origin = field_origin(ctx, grid);
+	let scale = field_scale(ctx, grid);
 	
-	for x in 0..TILES_PER_ROW as u8 {
-		for y in 0..TILES_PER_ROW as u8 {
-
-			let tile_pos = vec2(
-					width_half - TILE_FIELD_SIZE * 0.5 * GlobConst::SCALE + x as f32 * TILE_SIZE * GlobConst::SCALE,
-					TILE_FIELD_Y * GlobConst::SCALE + y as f32 * TILE_SIZE * GlobConst::SCALE
-				);
-			let mut dp = DrawParam::default()
-				.dest_rect(Rect::new(
-					// generally: TILE_FIELD_START + x + y
-					tile_pos.x,
-					tile_pos.y,
-
-					// width again because 30x30
-					// i think dest_rect varies the pixel rect ??
-					TILE_SIZE / images[0].width() as f32 * GlobConst::SCALE,
-					TILE_SIZE / images[0].width() as f32 * GlobConst::SCALE
-				));
-
-			canvas.draw(&images[0], dp);
-			
-			let state: State = states().lock().unwrap()[x as usize][y as usize];
-
-			match state {
-				State::Tree => {
-					draw_figurine(ctx, canvas, &images[1], &(tile_pos.x, tile_pos.y));
-				},
-				State::Chest => {
-					draw_figurine(ctx, canvas, &images[2], &(tile_pos.x, tile_pos.y));
-				}
-				State::Empty => (),
+	for (x, y, state) in grid.iter() {
+
+		let tile_pos = origin + vec2(x as f32, y as f32) * TILE_SIZE * scale;
+		let dp = DrawParam::default()
+			.dest_rect(Rect::new(
+				// generally: TILE_FIELD_START + x + y
+				tile_pos.x,
+				tile_pos.y,
+
+				// width again because 30x30
+				// i think dest_rect varies the pixel rect ??
+				TILE_SIZE / images[0].width() as f32 * scale,
+				TILE_SIZE / images[0].width() as f32 * scale
+			));
+
+		canvas.draw(&images[0], dp);
+
+		match state {
+			State::Tree => {
+				draw_figurine(ctx, canvas, grid, &images[1], &(tile_pos.x, tile_pos.y));
+			},
+			State::Chest => {
+				draw_figurine(ctx, canvas, grid, &images[2], &(tile_pos.x, tile_pos.y));
 			}
+			State::Empty => (),
 		}
 	}
 
 	Ok(())
 }
 
-pub fn draw_figurine(ctx: &mut Context, canvas: &mut Canvas, img: &Image, pos: &(f32, f32)) {
+// pos is the top left corner of the field, the figurine is scaled like the tiles of grid
+pub fn draw_figurine(ctx: &mut Context, canvas: &mut Canvas, grid: &Grid, img: &Image, pos: &(f32, f32)) {
+	draw_tinted_figurine(ctx, canvas, grid, img, pos, Color::WHITE);
+}
+
+// figurines that share a sprite, like the enemies, are told apart by their color
+pub fn draw_tinted_figurine(ctx: &mut Context, canvas: &mut Canvas, grid: &Grid, img: &Image, pos: &(f32, f32), color: Color) {
 	
+	let scale = field_scale(ctx, grid);
 	let (w, h) = (img.width(), img.height());
 	let dp = DrawParam::default().dest_rect(Rect::new(
-		pos.0 + TILE_SIZE * 0.5 * GlobConst::SCALE - w as f32 * 0.5 * GlobConst::SCALE,
-		pos.1 + TILE_SIZE * 0.5 * GlobConst::SCALE - h as f32 * 0.75 * GlobConst::SCALE,
-		GlobConst::SCALE, GlobConst::SCALE
+		pos.0 + TILE_SIZE * 0.5 * scale - w as f32 * 0.5 * scale,
+		pos.1 + TILE_SIZE * 0.5 * scale - h as f32 * 0.75 * scale,
+		scale, scale
 	))
-		.z((pos.1 + TILE_SIZE * 0.5 * GlobConst::SCALE - h as f32 * 0.75 * GlobConst::SCALE) as i32);
+		.color(color)
+		.z((pos.1 + TILE_SIZE * 0.5 * scale - h as f32 * 0.75 * scale) as i32);
 
 	canvas.draw(img, dp);
 }
 
 
-pub fn get_state(x: usize, y: usize) -> State {
-	assert!(x < TILES_PER_ROW as usize && y < TILES_PER_ROW as usize);
+pub fn grid_pos_to_screen(ctx: &mut Context, grid: &Grid, pos: &Vec2, screen_pos: &mut Vec2) -> GameResult {
+	assert!(pos.x >= 0.0 && pos.x < grid.width() as f32 && pos.y >= 0.0 && pos.y < grid.height() as f32);
 
-	states().lock().unwrap()[x][y]
-}
-
-
-pub fn grid_pos_to_screen(ctx: &mut Context, pos: &Vec2, screen_pos: &mut Vec2) -> GameResult {
-	assert!(pos.x >= 0.0 && pos.x < TILES_PER_ROW && pos.y >= 0.0 && pos.y < TILES_PER_ROW);
-	
-	let (width, height) = ctx.gfx.drawable_size();
-
-	screen_pos.x = width * 0.5 - TILE_FIELD_SIZE * 0.5 * GlobConst::SCALE + pos.x * TILE_SIZE * GlobConst::SCALE + TILE_SIZE * 0.5 * GlobConst::SCALE;
-	screen_pos.y = TILE_FIELD_Y * GlobConst::SCALE + pos.y * TILE_SIZE * GlobConst::SCALE + TILE_SIZE * 0.5 * GlobConst::SCALE;
+	*screen_pos = field_origin(ctx, grid) + (*pos + vec2(0.5, 0.5)) * field_size(ctx, grid);
 
 	Ok(())
 }
 
-pub fn mouse_pos_on_grid(ctx: &mut Context) -> GameResult<Option<Vec2>> {
+pub fn mouse_pos_on_grid(ctx: &mut Context, grid: &Grid) -> GameResult<Option<Vec2>> {
 	let mp = ctx.mouse.position();
-	let (width, height) = ctx.gfx.drawable_size();
-	let (width_half, height_half) = (width * 0.5, height * 0.5);
+	let origin = field_origin(ctx, grid);
+	let size = vec2(grid.width() as f32, grid.height() as f32);
+	let field_size = field_size(ctx, grid);
+	let field_end = origin + size * field_size;
 
 	// check if not over tilemap
-	if mp.x < width_half - TILE_FIELD_SIZE * 0.5 * GlobConst::SCALE ||
-		mp.x > width_half + TILE_FIELD_SIZE * 0.5 * GlobConst::SCALE ||
-		mp.y < TILE_FIELD_Y * GlobConst::SCALE ||
-		mp.y > TILE_FIELD_Y * GlobConst::SCALE + TILE_SIZE * TILES_PER_ROW * GlobConst::SCALE {
+	if mp.x < origin.x ||
+		mp.x > field_end.x ||
+		mp.y < origin.y ||
+		mp.y > field_end.y {
 		
 		return Ok(None);
 	}
 
-	let pos_on_grid = (vec2(mp.x, mp.y) - vec2(width_half - TILE_FIELD_SIZE as f32 * 0.5 * GlobConst::SCALE, TILE_FIELD_Y * GlobConst::SCALE)) / GlobConst::SCALE / TILE_SIZE - vec2(0.5, 0.5);
+	let pos_on_grid = (vec2(mp.x, mp.y) - origin) / field_size - vec2(0.5, 0.5);
 
-	Ok(Some(pos_on_grid.round().clamp(Vec2::ZERO, vec2(TILES_PER_ROW as f32 - 1.0, TILES_PER_ROW as f32 - 1.0))))
+	Ok(Some(pos_on_grid.round().clamp(Vec2::ZERO, size - Vec2::ONE)))
 }
@@ -0,0 +1,135 @@
+// undo and redo: the rules are small, so every step simply keeps a copy of them
+
+use crate::rules::Rules;
+
+
+pub struct History {
+	undo: Vec<Rules>,			// the state before every action, the last one is undone first
+	redo: Vec<Rules>,
+	undos_left: Option<u32>,	// None if the level has no undo limit
+}
+
+impl History {
+	pub fn new(limit: Option<u32>) -> History {
+		History {
+			undo: vec!(),
+			redo: vec!(),
+			undos_left: limit,
+		}
+	}
+
+	// call with the rules as they were before an action, a new action makes the redos pointless
+	pub fn record(self: &mut History, before: Rules) {
+		self.undo.push(before);
+		self.redo.clear();
+	}
+
+	pub fn can_undo(self: &History) -> bool {
+		!self.undo.is_empty() && self.undos_left != Some(0)
+	}
+
+	pub fn can_redo(self: &History) -> bool {
+		!self.redo.is_empty()
+	}
+
+	pub fn undos_left(self: &History) -> Option<u32> {
+		self.undos_left
+	}
+
+	// returns the rules to go back to, current is kept for redo
+	pub fn undo(self: &mut History, current: &Rules) -> Option<Rules> {
+		if !self.can_undo() {
+			return None;
+		}
+
+		let previous = self.undo.pop()?;
+		self.redo.push(current.clone());
+		self.undos_left = self.undos_left.map(|left| left - 1);
+
+		Some(previous)
+	}
+
+	// redoing doesn't give an undo back, it only replays what was undone
+	pub fn redo(self: &mut History, current: &Rules) -> Option<Rules> {
+		let next = self.redo.pop()?;
+		self.undo.push(current.clone());
+
+		Some(next)
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::rules::{Action, CardType, Grid};
+
+	// a hand of armor cards, every play is one step that can be taken back
+	fn armor_rules() -> Rules {
+		let mut rules = Rules::new();
+		rules.grid = Grid::new(3, 3);
+		rules.hand = vec![CardType::Armor; 3];
+
+		rules
+	}
+
+	// plays the first card and records the step like the game does
+	fn play(history: &mut History, rules: &mut Rules) {
+		history.record(rules.clone());
+		rules.perform(Action::PlayCard(0)).unwrap();
+	}
+
+	#[test]
+	fn undo_and_redo_walk_through_the_steps() {
+		let mut history = History::new(None);
+		let mut rules = armor_rules();
+		assert!(!history.can_undo() && !history.can_redo());
+
+		play(&mut history, &mut rules);
+		play(&mut history, &mut rules);
+		assert_eq!(rules.hand.len(), 1);
+
+		rules = history.undo(&rules).unwrap();
+		assert_eq!(rules.hand.len(), 2);
+		rules = history.undo(&rules).unwrap();
+		assert_eq!(rules.hand.len(), 3);
+		assert!(history.undo(&rules).is_none());
+
+		rules = history.redo(&rules).unwrap();
+		assert_eq!(rules.hand.len(), 2);
+		assert_eq!(rules.turn, 2);
+		assert!(history.can_undo() && history.can_redo());
+	}
+
+	#[test]
+	fn a_new_step_drops_the_redos() {
+		let mut history = History::new(None);
+		let mut rules = armor_rules();
+
+		play(&mut history, &mut rules);
+		rules = history.undo(&rules).unwrap();
+		assert!(history.can_redo());
+
+		play(&mut history, &mut rules);
+		assert!(!history.can_redo());
+		assert!(history.redo(&rules).is_none());
+	}
+
+	#[test]
+	fn the_undo_limit_counts_down_and_redo_gives_nothing_back() {
+		let mut history = History::new(Some(1));
+		let mut rules = armor_rules();
+
+		play(&mut history, &mut rules);
+		play(&mut history, &mut rules);
+
+		rules = history.undo(&rules).unwrap();
+		assert_eq!(history.undos_left(), Some(0));
+		assert!(!history.can_undo());
+		assert!(history.undo(&rules).is_none());
+
+		rules = history.redo(&rules).unwrap();
+		assert_eq!(rules.hand.len(), 1);
+		assert!(!history.can_undo());
+	}
+}
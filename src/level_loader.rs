@@ -0,0 +1,70 @@
+// the graphical side of starting a level: the card views, the player and the explainer on top of the Rules
+// parsing a level and building its Rules is in level_manager, which the command line tools use without ggez
+
+use ggez::Context;
+
+use std::error::Error;
+use std::path::Path;
+
+use crate::card::Card;
+use crate::explainer::Explainer;
+use crate::level_manager::{Level, read_level, is_explained};
+use crate::player::Player;
+use crate::rules::Rules;
+use crate::snapshot::Snapshot;
+
+
+// throws away everything the current level left behind
+pub fn unload_level(rules: &mut Rules, cards: &mut Vec<Card>, explainer: &mut Explainer) {
+	*rules = Rules::new();
+	cards.clear();
+	explainer.close();
+}
+
+// one card view for every card in the hand, e.g. after going back to an earlier state
+pub fn rebuild_cards(ctx: &mut Context, rules: &Rules, cards: &mut Vec<Card>) {
+	cards.clear();
+
+	// instantiate all the cards
+	for card_type in &rules.hand {
+		let mut card = Card::new(ctx, *card_type);
+		card.cost = rules.shown_cost(*card_type);
+		cards.push(card);
+	}
+}
+
+// (re)starts an already read level from scratch, also used to restart the current level
+pub fn start_level(ctx: &mut Context, level: &Level, seed: u64, rules: &mut Rules, cards: &mut Vec<Card>, player: &mut Player, explainer: &mut Explainer) {
+	unload_level(rules, cards, explainer);
+
+	*rules = level.rules(seed);
+
+	rebuild_cards(ctx, rules, cards);
+
+	player.snap_to(rules);
+
+	if let Some(t) = level.explain_on_startup.filter(|t| is_explained(*t)) {
+		explainer.explain(t);
+	}
+}
+
+pub fn load_level<P: AsRef<Path>>(ctx: &mut Context, path: P, seed_override: Option<u64>, rules: &mut Rules, cards: &mut Vec<Card>, player: &mut Player, explainer: &mut Explainer) -> Result<Level, Box<dyn Error>> {
+
+	let level = read_level(path)?;
+	let seed = level.choose_seed(seed_override);
+
+	start_level(ctx, &level, seed, rules, cards, player, explainer);
+
+	Ok(level)
+}
+
+// puts the views back on top of the restored rules, like start_level does for a fresh level
+pub fn restore(ctx: &mut Context, snapshot: &Snapshot, rules: &mut Rules, cards: &mut Vec<Card>, player: &mut Player, explainer: &mut Explainer) {
+	unload_level(rules, cards, explainer);
+
+	*rules = snapshot.rules.clone();
+
+	rebuild_cards(ctx, rules, cards);
+
+	player.snap_to(rules);
+}
@@ -1,79 +1,921 @@
-use serde::Deserialize;
+use serde::{Serialize, Deserialize};
 
-use ggez::{
-	glam::*,
-	Context
-};
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 
+use std::collections::BTreeMap;
 use std::error::Error;
-use std::fs::File;
-use std::io::BufReader;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{BufReader, Write};
 use std::path::Path;
 
-use crate::card::*;
-use crate::player::*;
-use crate::explainer::*;
-use crate::grid_drawer as GridDrawer;
+use crate::rules::{Rules, Grid, State, CardType, AttackCard};
+use crate::enemy::{Behaviour, EnemySpawn, Pattern};
+use crate::breakable::{BreakableKind, BreakableSpawn};
+use crate::tiled::parse_tiled_level;
 
 
-#[derive(Deserialize, Debug)]
+// a tile of the map, version 1 levels used numbers, version 2 levels names
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Tile {
+	Empty,
+	Player,
+	Tree,
+	Chest,
+}
+
+impl Tile {
+	// the numbers of version 1 levels
+	pub fn from_code(code: u8) -> Option<Tile> {
+		match code {
+			0 => Some(Tile::Empty),
+			1 => Some(Tile::Player),
+			2 => Some(Tile::Tree),
+			3 => Some(Tile::Chest),
+			_ => None,
+		}
+	}
+
+	// the characters of the default legend, also used by text levels
+	pub fn to_char(self: Tile) -> char {
+		DEFAULT_LEGEND.iter().find(|(_, t)| *t == self).map_or('.', |(c, _)| *c)
+	}
+
+	pub fn from_char(c: char) -> Option<Tile> {
+		DEFAULT_LEGEND.iter().find(|(lc, _)| *lc == c).map(|(_, t)| *t)
+	}
+}
+
+pub const LEVEL_VERSION: u32 = 2;
+
+// the legend written into new level files
+pub const DEFAULT_LEGEND: [(char, Tile); 4] = [('.', Tile::Empty), ('@', Tile::Player), ('T', Tile::Tree), ('C', Tile::Chest)];
+
+// the card types the Explainer has an explanation for, one for all Attack cards
+pub const EXPLAINED_CARDS: [CardType; 3] = [CardType::Move, CardType::Key, CardType::Attack(AttackCard::MELEE)];
+
+pub fn is_explained(card: CardType) -> bool {
+	EXPLAINED_CARDS.iter().any(|explained| explained.index() == card.index())
+}
+
+// explain_on_startup has to name a card with an explanation, otherwise nothing would be shown
+pub fn explained_card(card: CardType) -> Result<CardType, String> {
+	if !is_explained(card) {
+		return Err(format!("explain_on_startup: there is no explanation for {}", card));
+	}
+
+	Ok(card)
+}
+
+
+// a level as the game uses it, no matter which version the file had
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
 pub struct Level {
-	map: Vec<Vec<u8>>,
-	cards: Vec<String>,
-	cards_on_hand: u8,
-	explain_on_startup: String,
+	pub width: usize,
+	pub height: usize,
+	pub map: Vec<Vec<Tile>>,
+	pub cards: Vec<CardType>,
+	pub cards_on_hand: u8,
+	pub explain_on_startup: Option<CardType>,
+	pub shuffle: bool,				// shuffle the deck before dealing
+	pub seed: Option<u64>,			// fixed shuffle seed, random if there is none
+	#[serde(default)]
+	pub undo_limit: Option<u32>,	// how often a move can be taken back, unlimited if there is none
+	#[serde(default)]
+	pub enemies: Vec<EnemySpawn>,
+	#[serde(default)]
+	pub energy_per_turn: Option<u32>,	// turns with energy and card costs, none: every action is a turn
+	#[serde(default)]
+	pub card_costs: BTreeMap<CardType, u32>,
+	#[serde(default)]
+	pub turn_limit: Option<u32>,		// the chest has to be opened within this many turns
+	#[serde(default)]
+	pub breakables: Vec<BreakableSpawn>,
 }
 
-pub fn load_level<P: AsRef<Path>>(ctx: &mut Context, path: P, cards: &mut Vec<Card>, player: &mut Player, explainer: &mut Explainer) -> Result<Level, Box<dyn Error>> {
-	
-	let current_dir = std::env::current_dir().unwrap();
+// version 1 files: numbers as tiles and free-form card names
+// the first files had no size, it comes from the map then
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct LevelV1 {
+	#[serde(default)]
+	pub version: Option<u32>,
+	#[serde(default)]
+	pub width: Option<usize>,
+	#[serde(default)]
+	pub height: Option<usize>,
+	pub map: Vec<Vec<u8>>,
+	pub cards: Vec<String>,
+	pub cards_on_hand: u8,
+	#[serde(default)]
+	pub explain_on_startup: String,
+	#[serde(default)]
+	pub shuffle: bool,
+	pub seed: Option<u64>,
+}
 
-	let file = File::open(current_dir.join(path))?;
-	let reader = BufReader::new(file);
+// version 2 files: the map is made of strings, every character is looked up in the legend
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct LevelV2 {
+	pub version: u32,
+	pub width: usize,
+	pub height: usize,
+	pub legend: BTreeMap<char, Tile>,
+	pub map: Vec<String>,
+	pub cards: Vec<CardType>,
+	pub cards_on_hand: u8,
+	#[serde(default)]
+	pub explain_on_startup: Option<CardType>,
+	#[serde(default)]
+	pub shuffle: bool,
+	#[serde(default)]
+	pub seed: Option<u64>,
+	#[serde(default)]
+	pub undo_limit: Option<u32>,
+	#[serde(default)]
+	pub enemies: Vec<EnemySpawn>,
+	#[serde(default)]
+	pub energy_per_turn: Option<u32>,
+	#[serde(default)]
+	pub card_costs: BTreeMap<CardType, u32>,
+	#[serde(default)]
+	pub turn_limit: Option<u32>,
+	#[serde(default)]
+	pub breakables: Vec<BreakableSpawn>,
+}
+
+#[derive(Deserialize)]
+struct VersionOnly {
+	#[serde(default = "first_version")]
+	version: u32,
+}
+
+fn first_version() -> u32 {
+	1
+}
+
+// the manifest of a level pack, pack.json in the pack's directory
+#[derive(Deserialize, Debug)]
+pub struct Manifest {
+	#[serde(default)]
+	pub name: String,
+	#[serde(default)]
+	pub author: String,
+	pub levels: Vec<PackLevel>,
+}
 
-	let level: Level = serde_json::from_reader(reader)?;
+// a level of a pack, old manifests only listed the file
+#[derive(Deserialize, Clone, Debug)]
+#[serde(from = "PackLevelEntry")]
+pub struct PackLevel {
+	pub file: String,				// relative to the pack's directory
+	pub title: String,
+	pub par: Option<u32>,			// card plays of a good run
+	pub hint: Option<String>,
+}
 
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PackLevelEntry {
+	File(String),
+	Level {
+		file: String,
+		title: Option<String>,
+		par: Option<u32>,
+		hint: Option<String>,
+	},
+}
 
-	// instantiate all the cards
-	for card in &level.cards {
-		let card_type = match card.to_uppercase().as_ref() {
-			"HEALTH" => Some(CardType::Health),
-			"ARMOR" => Some(CardType::Armor),
-			"MOVE" => Some(CardType::Move),
-			"KEY" => Some(CardType::Key),
-			_ => None
+impl From<PackLevelEntry> for PackLevel {
+	fn from(entry: PackLevelEntry) -> PackLevel {
+		let (file, title, par, hint) = match entry {
+			PackLevelEntry::File(file) => (file, None, None, None),
+			PackLevelEntry::Level { file, title, par, hint } => (file, title, par, hint),
 		};
 
-		if let Some(ct) = card_type {
-			cards.push(Card::new(ctx, ct));
+		// without a title the file name has to do
+		let title = title.unwrap_or_else(|| Path::new(&file).file_stem().map_or(file.clone(), |stem| stem.to_string_lossy().to_string()));
+
+		PackLevel { file, title, par, hint }
+	}
+}
+
+impl LevelV1 {
+	// the declared size, or the size of the map if there is none
+	pub fn size(self: &LevelV1) -> (usize, usize) {
+		let width = self.width.unwrap_or_else(|| self.map.first().map_or(0, |row| row.len()));
+		let height = self.height.unwrap_or(self.map.len());
+
+		(width, height)
+	}
+
+	// anything unknown is an error now instead of being dropped
+	pub fn migrate(self: LevelV1) -> Result<Level, Box<dyn Error>> {
+		let mut map = vec!();
+		for (y, row) in self.map.iter().enumerate() {
+			let mut tiles = vec!();
+			for (x, code) in row.iter().enumerate() {
+				tiles.push(Tile::from_code(*code).ok_or_else(|| format!("unknown tile code {} at ({}, {})", code, x, y))?);
+			}
+			map.push(tiles);
+		}
+
+		let mut cards = vec!();
+		for card in &self.cards {
+			cards.push(card_type_from_name(card).ok_or_else(|| format!("unknown card \"{}\"", card))?);
+		}
+
+		// an empty string explains nothing
+		let explain_on_startup = match self.explain_on_startup.as_str() {
+			"" => None,
+			name => Some(explained_card(card_type_from_name(name).ok_or_else(|| format!("unknown card \"{}\" in explain_on_startup", name))?)?),
+		};
+
+		let (width, height) = self.size();
+
+		Ok(Level {
+			width,
+			height,
+			map,
+			cards,
+			cards_on_hand: self.cards_on_hand,
+			explain_on_startup,
+			shuffle: self.shuffle,
+			seed: self.seed,
+			undo_limit: None,
+			enemies: vec!(),
+			energy_per_turn: None,
+			card_costs: BTreeMap::new(),
+			turn_limit: None,
+			breakables: vec!(),
+		})
+	}
+}
+
+impl LevelV2 {
+	pub fn into_level(self: LevelV2) -> Result<Level, Box<dyn Error>> {
+		let mut map = vec!();
+		for (y, row) in self.map.iter().enumerate() {
+			let mut tiles = vec!();
+			for c in row.chars() {
+				tiles.push(*self.legend.get(&c).ok_or_else(|| format!("row {} uses '{}', which isn't in the legend", y, c))?);
+			}
+			map.push(tiles);
 		}
 
+		Ok(Level {
+			width: self.width,
+			height: self.height,
+			map,
+			cards: self.cards,
+			cards_on_hand: self.cards_on_hand,
+			explain_on_startup: self.explain_on_startup.map(explained_card).transpose()?,
+			shuffle: self.shuffle,
+			seed: self.seed,
+			undo_limit: self.undo_limit,
+			enemies: self.enemies,
+			energy_per_turn: self.energy_per_turn,
+			card_costs: self.card_costs,
+			turn_limit: self.turn_limit,
+			breakables: self.breakables,
+		})
+	}
+}
+
+// Fisher-Yates on the raw ChaCha8 numbers, which are the same on every platform and rand version
+// rand's own shuffle and range sampling may change between versions, saved replays depend on this order
+// the modulo is a little biased, but not noticeably for decks of at most 255 cards
+pub fn shuffle_deck(deck: &mut [CardType], seed: u64) {
+	let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+	for i in (1..deck.len()).rev() {
+		let j = (rng.next_u64() % (i as u64 + 1)) as usize;
+		deck.swap(i, j);
+	}
+}
+
+impl Level {
+	// fresh rules for the start of this level, doesn't need a Context
+	// the same seed always deals the cards in the same order, see shuffle_deck
+	pub fn rules(self: &Level, seed: u64) -> Rules {
+		let mut rules = Rules::new();
+		rules.seed = seed;
+		rules.hand_size = self.cards_on_hand as usize;
+
+		// the whole deck goes onto the draw pile, the first card in the file is drawn first
+		rules.draw_pile = self.cards.iter().rev().copied().collect();
+
+		if self.shuffle {
+			shuffle_deck(&mut rules.draw_pile, seed);
+		}
+
+		rules.draw_cards();
+
+		rules.grid = self.grid();
+		if let Some(spawn) = self.player_spawn() {
+			rules.player_pos = spawn;
+		}
+
+		rules.enemies = self.enemies.iter().map(EnemySpawn::enemy).collect();
+
+		rules.energy_per_turn = self.energy_per_turn;
+		rules.energy = self.energy_per_turn.unwrap_or(0);
+		rules.card_costs = self.card_costs.clone();
+		rules.turn_limit = self.turn_limit;
+
+		rules.breakables = self.breakables.iter().map(BreakableSpawn::breakable).collect();
+
+		rules
 	}
 
 	// add tilemap thing
-	for (y, row) in level.map.iter().enumerate() {
-		for (x, element) in row.iter().enumerate() {
-			match element {
-				1 => {
-					player.pos = vec2(x as f32, y as f32);
-					player.target_pos = vec2(x as f32, y as f32);
-				},
-				2 => GridDrawer::set_state_at(x as usize, y as usize, GridDrawer::State::Tree),
-				3 => GridDrawer::set_state_at(x as usize, y as usize, GridDrawer::State::Chest),
-				_ => (),
+	pub fn grid(self: &Level) -> Grid {
+		let mut grid = Grid::new(self.width, self.height);
+
+		for (y, row) in self.map.iter().enumerate() {
+			for (x, tile) in row.iter().enumerate() {
+				match tile {
+					Tile::Tree => grid.set(x as i32, y as i32, State::Tree),
+					Tile::Chest => grid.set(x as i32, y as i32, State::Chest),
+					_ => (),
+				}
+			}
+		}
+
+		grid
+	}
+
+	// the last player tile in the map, if there is one
+	pub fn player_spawn(self: &Level) -> Option<(i32, i32)> {
+		let mut spawn = None;
+
+		for (y, row) in self.map.iter().enumerate() {
+			for (x, tile) in row.iter().enumerate() {
+				if *tile == Tile::Player {
+					spawn = Some((x as i32, y as i32));
+				}
+			}
+		}
+
+		spawn
+	}
+
+	// the map has to match the declared size and the enemies and breakables have to be on it, otherwise rules() would index out of the grid
+	pub fn check_size(self: &Level) -> Result<(), Box<dyn Error>> {
+		if self.width == 0 || self.height == 0 {
+			return Err(format!("level size {}x{} is empty", self.width, self.height).into());
+		}
+
+		if self.map.len() != self.height {
+			return Err(format!("map has {} rows, but the level height is {}", self.map.len(), self.height).into());
+		}
+
+		for (y, row) in self.map.iter().enumerate() {
+			if row.len() != self.width {
+				return Err(format!("row {} of the map has {} tiles, but the level width is {}", y, row.len(), self.width).into());
+			}
+		}
+
+		for enemy in &self.enemies {
+			if enemy.x < 0 || enemy.y < 0 || enemy.x as usize >= self.width || enemy.y as usize >= self.height {
+				return Err(format!("the {:?} at ({}, {}) is outside of the map", enemy.behaviour, enemy.x, enemy.y).into());
+			}
+		}
+
+		for breakable in &self.breakables {
+			if breakable.x < 0 || breakable.y < 0 || breakable.x as usize >= self.width || breakable.y as usize >= self.height {
+				return Err(format!("the {:?} at ({}, {}) is outside of the map", breakable.kind, breakable.x, breakable.y).into());
 			}
 		}
+
+		Ok(())
+	}
+
+	// a seed given from outside (e.g. the command line) wins over the one in the file
+	pub fn choose_seed(self: &Level, seed_override: Option<u64>) -> u64 {
+		seed_override.or(self.seed).unwrap_or_else(rand::random)
+	}
+}
+
+// the json layout of the hand written levels: one map row per line, tabs
+// always writes the newest version
+pub fn level_to_json(level: &Level) -> String {
+	let quote = |s: &str| serde_json::to_string(s).unwrap();
+
+	let legend: Vec<String> = DEFAULT_LEGEND.iter()
+		.map(|(c, tile)| format!("\t\t{}: \"{:?}\"", quote(&c.to_string()), tile))
+		.collect();
+	let rows: Vec<String> = level.map.iter()
+		.map(|row| format!("\t\t{}", quote(&row.iter().map(|tile| tile.to_char()).collect::<String>())))
+		.collect();
+	let cards: Vec<String> = level.cards.iter()
+		.map(|card| format!("\t\t{}", quote(&card.to_string())))
+		.collect();
+	let enemies: Vec<String> = level.enemies.iter()
+		.map(|enemy| format!("\t\t{}", enemy_to_json(enemy)))
+		.collect();
+	let breakables: Vec<String> = level.breakables.iter()
+		.map(|breakable| format!("\t\t{}", breakable_to_json(breakable)))
+		.collect();
+
+	let mut json = String::from("{\n");
+	json += &format!("\t\"version\": {},\n", LEVEL_VERSION);
+	json += &format!("\t\"width\": {},\n", level.width);
+	json += &format!("\t\"height\": {},\n", level.height);
+	json += &format!("\t\"legend\": {{\n{}\n\t}},\n", legend.join(",\n"));
+	json += &format!("\t\"map\": [\n{}\n\t],\n", rows.join(",\n"));
+	json += &format!("\t\"cards\": [\n{}\n\t],\n", cards.join(",\n"));
+
+	if !enemies.is_empty() {
+		json += &format!("\t\"enemies\": [\n{}\n\t],\n", enemies.join(",\n"));
+	}
+	if !breakables.is_empty() {
+		json += &format!("\t\"breakables\": [\n{}\n\t],\n", breakables.join(",\n"));
+	}
+
+	if level.shuffle {
+		json += "\t\"shuffle\": true,\n";
+	}
+	if let Some(seed) = level.seed {
+		json += &format!("\t\"seed\": {},\n", seed);
+	}
+	if let Some(card) = level.explain_on_startup {
+		json += &format!("\t\"explain_on_startup\": {},\n", quote(&card.to_string()));
+	}
+	if let Some(limit) = level.undo_limit {
+		json += &format!("\t\"undo_limit\": {},\n", limit);
+	}
+	if let Some(energy) = level.energy_per_turn {
+		json += &format!("\t\"energy_per_turn\": {},\n", energy);
+	}
+	if !level.card_costs.is_empty() {
+		let costs: Vec<String> = level.card_costs.iter().map(|(card, cost)| format!("{}: {}", quote(&card.to_string()), cost)).collect();
+		json += &format!("\t\"card_costs\": {{{}}},\n", costs.join(", "));
+	}
+	if let Some(limit) = level.turn_limit {
+		json += &format!("\t\"turn_limit\": {},\n", limit);
+	}
+
+	json += &format!("\t\"cards_on_hand\": {}\n", level.cards_on_hand);
+	json += "}\n";
+
+	json
+}
+
+// one enemy per line, attack and range only if the level sets them
+fn enemy_to_json(enemy: &EnemySpawn) -> String {
+	let mut json = format!("{{\"behaviour\": \"{:?}\", \"x\": {}, \"y\": {}", enemy.behaviour, enemy.x, enemy.y);
+
+	if let Some(attack) = enemy.attack {
+		json += &format!(", \"attack\": {}", attack);
+	}
+	if let Some(range) = enemy.range {
+		json += &format!(", \"range\": {}", range);
+	}
+	if !enemy.pattern.is_empty() {
+		let pattern: Vec<String> = enemy.pattern.iter().map(|p| format!("\"{:?}\"", p)).collect();
+		json += &format!(", \"pattern\": [{}]", pattern.join(", "));
+	}
+
+	json + "}"
+}
+
+fn breakable_to_json(breakable: &BreakableSpawn) -> String {
+	let mut json = format!("{{\"kind\": \"{:?}\", \"x\": {}, \"y\": {}", breakable.kind, breakable.x, breakable.y);
+
+	if let Some(health) = breakable.health {
+		json += &format!(", \"health\": {}", health);
+	}
+
+	json + "}"
+}
+
+// "Crate 3 2" or "Dummy 1 1 health=6" in text levels
+fn breakable_to_text(breakable: &BreakableSpawn) -> String {
+	let mut text = format!("{:?} {} {}", breakable.kind, breakable.x, breakable.y);
+
+	if let Some(health) = breakable.health {
+		text += &format!(" health={}", health);
+	}
+
+	text
+}
+
+fn parse_breakable(text: &str) -> Result<BreakableSpawn, String> {
+	let mut parts = text.split_whitespace();
+
+	let name = parts.next().ok_or("a breakable needs a kind and a position")?;
+	let kind = BreakableKind::from_name(name).ok_or_else(|| format!("unknown breakable \"{}\"", name))?;
+
+	let mut coordinate = || -> Result<i32, String> {
+		let value = parts.next().ok_or("a breakable needs a kind and a position")?;
+		value.parse().map_err(|_| format!("\"{}\" isn't a coordinate", value))
+	};
+	let (x, y) = (coordinate()?, coordinate()?);
+
+	let mut breakable = BreakableSpawn { kind, x, y, health: None };
+
+	for option in parts {
+		match option.split_once('=') {
+			Some(("health", value)) => breakable.health = Some(value.parse().map_err(|_| format!("health can't be \"{}\"", value))?),
+			_ => return Err(format!("unknown breakable option \"{}\"", option)),
+		}
+	}
+
+	Ok(breakable)
+}
+
+// "Ranged 4 2 attack=2 range=3" or "Figurine 1 1 pattern=Cross,Wait" in text levels
+fn enemy_to_text(enemy: &EnemySpawn) -> String {
+	let mut text = format!("{:?} {} {}", enemy.behaviour, enemy.x, enemy.y);
+
+	if let Some(attack) = enemy.attack {
+		text += &format!(" attack={}", attack);
+	}
+	if let Some(range) = enemy.range {
+		text += &format!(" range={}", range);
+	}
+	if !enemy.pattern.is_empty() {
+		let pattern: Vec<String> = enemy.pattern.iter().map(|p| format!("{:?}", p)).collect();
+		text += &format!(" pattern={}", pattern.join(","));
+	}
+
+	text
+}
+
+// "Cross, Wait, Ring", used by text levels and Tiled maps
+pub fn parse_pattern(text: &str) -> Result<Vec<Pattern>, String> {
+	text.split(',')
+		.map(str::trim)
+		.filter(|name| !name.is_empty())
+		.map(|name| Pattern::from_name(name).ok_or_else(|| format!("unknown pattern \"{}\"", name)))
+		.collect()
+}
+
+// "Move=2, Attack Line 3=1" in text levels and Tiled maps
+pub fn card_costs_to_text(costs: &BTreeMap<CardType, u32>) -> String {
+	costs.iter().map(|(card, cost)| format!("{}={}", card, cost)).collect::<Vec<String>>().join(", ")
+}
+
+pub fn parse_card_costs(text: &str) -> Result<BTreeMap<CardType, u32>, String> {
+	let mut costs = BTreeMap::new();
+
+	for entry in text.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+		let (name, cost) = entry.split_once('=').ok_or_else(|| format!("\"{}\" should look like Move=2", entry))?;
+		let card = CardType::from_name(name)?;
+		let cost = cost.trim().parse().map_err(|_| format!("the cost of {} can't be \"{}\"", card, cost.trim()))?;
+
+		costs.insert(card, cost);
+	}
+
+	Ok(costs)
+}
+
+fn parse_enemy(text: &str) -> Result<EnemySpawn, String> {
+	let mut parts = text.split_whitespace();
+
+	let name = parts.next().ok_or("an enemy needs a behaviour and a position")?;
+	let behaviour = Behaviour::from_name(name).ok_or_else(|| format!("unknown enemy behaviour \"{}\"", name))?;
+
+	let mut coordinate = || -> Result<i32, String> {
+		let value = parts.next().ok_or("an enemy needs a behaviour and a position")?;
+		value.parse().map_err(|_| format!("\"{}\" isn't a coordinate", value))
+	};
+	let (x, y) = (coordinate()?, coordinate()?);
+
+	let mut enemy = EnemySpawn { behaviour, x, y, attack: None, range: None, pattern: vec!() };
+
+	for option in parts {
+		match option.split_once('=') {
+			Some(("attack", value)) => enemy.attack = Some(value.parse().map_err(|_| format!("attack can't be \"{}\"", value))?),
+			Some(("range", value)) => enemy.range = Some(value.parse().map_err(|_| format!("range can't be \"{}\"", value))?),
+			Some(("pattern", value)) => enemy.pattern = parse_pattern(value)?,
+			_ => return Err(format!("unknown enemy option \"{}\"", option)),
+		}
+	}
+
+	Ok(enemy)
+}
+
+// header lines first, then one line per map row:
+//
+//   cards: Move, Key
+//   cards_on_hand: 1
+//   explain_on_startup: Key
+//   enemy: Chaser 5 0
+//   energy_per_turn: 2
+//   card_costs: Health=1
+//   turn_limit: 5
+//   breakable: Crate 3 1
+//
+//   ...@C..
+pub fn level_to_text(level: &Level) -> String {
+	let cards: Vec<String> = level.cards.iter().map(CardType::to_string).collect();
+
+	let mut text = format!("cards: {}\n", cards.join(", "));
+	text += &format!("cards_on_hand: {}\n", level.cards_on_hand);
+
+	if let Some(card) = level.explain_on_startup {
+		text += &format!("explain_on_startup: {}\n", card);
+	}
+	if level.shuffle {
+		text += "shuffle: true\n";
+	}
+	if let Some(seed) = level.seed {
+		text += &format!("seed: {}\n", seed);
+	}
+	if let Some(limit) = level.undo_limit {
+		text += &format!("undo_limit: {}\n", limit);
+	}
+	for enemy in &level.enemies {
+		text += &format!("enemy: {}\n", enemy_to_text(enemy));
+	}
+	if let Some(energy) = level.energy_per_turn {
+		text += &format!("energy_per_turn: {}\n", energy);
+	}
+	if !level.card_costs.is_empty() {
+		text += &format!("card_costs: {}\n", card_costs_to_text(&level.card_costs));
+	}
+	if let Some(limit) = level.turn_limit {
+		text += &format!("turn_limit: {}\n", limit);
+	}
+	for breakable in &level.breakables {
+		text += &format!("breakable: {}\n", breakable_to_text(breakable));
+	}
+
+	text += "\n";
+	for row in &level.map {
+		text += &row.iter().map(|tile| tile.to_char()).collect::<String>();
+		text += "\n";
+	}
+
+	text
+}
+
+impl fmt::Display for Level {
+	fn fmt(self: &Level, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}", level_to_text(self))
 	}
-	
-	let ct = match level.explain_on_startup.to_uppercase().as_ref() {
-		"MOVE" => Some(CardType::Move),
-		"KEY" => Some(CardType::Key),
-		_ => None
+}
+
+// lines with a ':' belong to the header, every other non-empty line is a map row
+pub fn parse_text_level(text: &str) -> Result<Level, Box<dyn Error>> {
+	let mut level = Level {
+		width: 0,
+		height: 0,
+		map: vec!(),
+		cards: vec!(),
+		cards_on_hand: 0,
+		explain_on_startup: None,
+		shuffle: false,
+		seed: None,
+		undo_limit: None,
+		enemies: vec!(),
+		energy_per_turn: None,
+		card_costs: BTreeMap::new(),
+		turn_limit: None,
+		breakables: vec!(),
+	};
+	let mut has_cards = false;
+	let mut has_hand = false;
+
+	let card_type = |name: &str, line: usize| -> Result<CardType, Box<dyn Error>> {
+		CardType::from_name(name).map_err(|e| format!("line {}: {}", line, e).into())
 	};
 
-	if let Some(t) = ct {
-		explainer.explain(t);
+	for (i, line) in text.lines().enumerate() {
+		let (line_number, line) = (i + 1, line.trim_end());
+
+		if line.is_empty() {
+			continue;
+		}
+
+		let Some((key, value)) = line.split_once(':') else {
+			let mut row = vec!();
+			for c in line.chars() {
+				row.push(Tile::from_char(c).ok_or_else(|| format!("line {}: unknown tile '{}'", line_number, c))?);
+			}
+			level.map.push(row);
+			continue;
+		};
+
+		let value = value.trim();
+		match key.trim() {
+			"cards" => {
+				has_cards = true;
+				for name in value.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+					level.cards.push(card_type(name, line_number)?);
+				}
+			},
+			"cards_on_hand" => {
+				has_hand = true;
+				level.cards_on_hand = value.parse().map_err(|_| format!("line {}: cards_on_hand can't be \"{}\"", line_number, value))?;
+			},
+			"explain_on_startup" => {
+				let card = card_type(value, line_number)?;
+				level.explain_on_startup = Some(explained_card(card).map_err(|e| format!("line {}: {}", line_number, e))?);
+			},
+			"shuffle" => level.shuffle = value.parse().map_err(|_| format!("line {}: shuffle can't be \"{}\"", line_number, value))?,
+			"seed" => level.seed = Some(value.parse().map_err(|_| format!("line {}: seed can't be \"{}\"", line_number, value))?),
+			"undo_limit" => level.undo_limit = Some(value.parse().map_err(|_| format!("line {}: undo_limit can't be \"{}\"", line_number, value))?),
+			"enemy" => level.enemies.push(parse_enemy(value).map_err(|e| format!("line {}: {}", line_number, e))?),
+			"energy_per_turn" => level.energy_per_turn = Some(value.parse().map_err(|_| format!("line {}: energy_per_turn can't be \"{}\"", line_number, value))?),
+			"card_costs" => level.card_costs = parse_card_costs(value).map_err(|e| format!("line {}: {}", line_number, e))?,
+			"turn_limit" => level.turn_limit = Some(value.parse().map_err(|_| format!("line {}: turn_limit can't be \"{}\"", line_number, value))?),
+			"breakable" => level.breakables.push(parse_breakable(value).map_err(|e| format!("line {}: {}", line_number, e))?),
+			other => return Err(format!("line {}: unknown header \"{}\"", line_number, other).into()),
+		}
+	}
+
+	if !has_cards || !has_hand {
+		return Err("the header needs cards and cards_on_hand".into());
 	}
 
+	level.height = level.map.len();
+	level.width = level.map.first().map_or(0, Vec::len);
+	level.check_size()?;
+
 	Ok(level)
 }
+
+fn is_text_level(path: &Path) -> bool {
+	path.extension().is_some_and(|ext| ext == "txt")
+}
+
+pub fn is_tiled_level(path: &Path) -> bool {
+	path.extension().is_some_and(|ext| ext == "tmj")
+}
+
+// .txt files are written as text levels, everything else as json
+pub fn write_level<P: AsRef<Path>>(path: P, level: &Level) -> Result<(), Box<dyn Error>> {
+	if is_tiled_level(path.as_ref()) {
+		return Err("Tiled maps can only be imported, save the level as .json or .txt".into());
+	}
+
+	let contents = if is_text_level(path.as_ref()) { level_to_text(level) } else { level_to_json(level) };
+
+	let mut file = File::create(path)?;
+	file.write_all(contents.as_bytes())?;
+
+	Ok(())
+}
+
+// card names in version 1 files, case doesn't matter
+pub fn card_type_from_name(name: &str) -> Option<CardType> {
+	CardType::from_name(name).ok()
+}
+
+// files without a version are version 1
+pub fn level_version(text: &str) -> Result<u32, serde_json::Error> {
+	let version: VersionOnly = serde_json::from_str(text)?;
+
+	Ok(version.version)
+}
+
+// parses any version, older ones are migrated
+pub fn parse_level(text: &str) -> Result<Level, Box<dyn Error>> {
+	let level = match level_version(text)? {
+		1 => serde_json::from_str::<LevelV1>(text)?.migrate()?,
+		2 => serde_json::from_str::<LevelV2>(text)?.into_level()?,
+		v => return Err(format!("unsupported level version {}", v).into()),
+	};
+
+	level.check_size()?;
+
+	Ok(level)
+}
+
+// reads a level without touching anything graphical
+pub fn read_level<P: AsRef<Path>>(path: P) -> Result<Level, Box<dyn Error>> {
+	let current_dir = std::env::current_dir()?;
+
+	let full_path = current_dir.join(&path);
+	let text = fs::read_to_string(&full_path)?;
+
+	if is_text_level(path.as_ref()) {
+		return parse_text_level(&text);
+	}
+
+	if is_tiled_level(path.as_ref()) {
+		return parse_tiled_level(&text, full_path.parent().unwrap_or(&current_dir));
+	}
+
+	parse_level(&text)
+}
+
+pub fn read_manifest<P: AsRef<Path>>(path: P) -> Result<Manifest, Box<dyn Error>> {
+	let current_dir = std::env::current_dir()?;
+
+	let file = File::open(current_dir.join(path))?;
+	let reader = BufReader::new(file);
+
+	let manifest: Manifest = serde_json::from_reader(reader)?;
+
+	Ok(manifest)
+}
+
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// resources/levels/level1.json as the first version of the game shipped it, without a size
+	const FIRST_LEVEL_V1: &str = include_str!("../resources/fixtures/level1_v1.json");
+
+	#[test]
+	fn migrates_the_first_level() {
+		assert_eq!(level_version(FIRST_LEVEL_V1).unwrap(), 1);
+
+		let level = parse_level(FIRST_LEVEL_V1).unwrap();
+		assert_eq!((level.width, level.height), (7, 7));
+		assert_eq!(level.player_spawn(), Some((3, 3)));
+		assert_eq!(level.map[3][4], Tile::Chest);
+		assert_eq!(level.cards, vec!(CardType::Key));
+		assert_eq!(level.cards_on_hand, 1);
+		assert_eq!(level.explain_on_startup, Some(CardType::Key));
+		assert!(!level.shuffle);
+	}
+
+	#[test]
+	fn v1_size_has_to_match_the_map() {
+		let text = "{ \"width\": 3, \"map\": [[0, 1], [0, 3]], \"cards\": [\"Key\"], \"cards_on_hand\": 1 }";
+		assert!(parse_level(text).is_err());
+
+		let text = "{ \"width\": 2, \"height\": 2, \"map\": [[0, 1], [0, 3]], \"cards\": [\"Key\"], \"cards_on_hand\": 1 }";
+		assert_eq!(parse_level(text).unwrap().explain_on_startup, None);
+	}
+
+	// the deal order of a seed must never change, saved replays and shared seeds depend on it
+	#[test]
+	fn a_seed_always_deals_the_same_cards() {
+		let text = "{ \"version\": 2, \"width\": 2, \"height\": 1, \"legend\": { \".\": \"Empty\", \"@\": \"Player\" }, \"map\": [\"@.\"], \"cards\": [\"Move\", \"Armor\", \"Health\", \"Key\", \"Attack\", \"Move\", \"Armor\", \"Health\"], \"cards_on_hand\": 3, \"shuffle\": true }";
+		let level = parse_level(text).unwrap();
+
+		let rules = level.rules(42);
+		assert_eq!(rules.hand, vec!(CardType::Armor, CardType::Health, CardType::Key));
+		assert_eq!(rules.draw_pile, vec!(CardType::Health, CardType::Attack(AttackCard::MELEE), CardType::Move, CardType::Armor, CardType::Move));
+
+		assert_eq!(level.rules(42).hand, rules.hand);
+	}
+
+	#[test]
+	fn attack_cards_keep_their_shape_in_both_formats() {
+		let text = "cards: Move, Attack Line 3, Attack\ncards_on_hand: 2\ncard_costs: Attack Line 3=2\nbreakable: Crate 2 0\n\n@..C\n";
+		let level = parse_text_level(text).unwrap();
+		assert_eq!(level.cards[1], CardType::from_name("Attack Line 3").unwrap());
+		assert_eq!(level.cards[2], CardType::Attack(AttackCard::MELEE));
+
+		assert!(level_to_json(&level).contains("\"Attack Line 3\": 2"));
+		assert_eq!(parse_level(&level_to_json(&level)).unwrap(), level);
+		assert_eq!(parse_text_level(&level_to_text(&level)).unwrap(), level);
+	}
+
+	// everything a json level can hold has to survive being written as text
+	#[test]
+	fn bundled_levels_survive_the_text_format() {
+		for pack in ["basics", "ambush", "training"] {
+			let dir = Path::new(crate::pack::PACKS_DIR).join(pack);
+			let manifest = read_manifest(dir.join("pack.json")).unwrap();
+
+			for entry in &manifest.levels {
+				let level = read_level(dir.join(&entry.file)).unwrap();
+				assert_eq!(parse_text_level(&level_to_text(&level)).unwrap(), level, "{}/{}", pack, entry.file);
+			}
+		}
+	}
+
+	#[test]
+	fn text_levels_report_the_broken_line() {
+		let error = parse_text_level("cards: Key\ncards_on_hand: 1\n\n@.X\n").unwrap_err();
+		assert_eq!(error.to_string(), "line 4: unknown tile 'X'");
+
+		let error = parse_text_level("cards: Key, Sword\ncards_on_hand: 1\n\n@.C\n").unwrap_err();
+		assert!(error.to_string().starts_with("line 1: "), "{}", error);
+
+		assert!(parse_text_level("cards: Key\n\n@.C\n").is_err());
+		assert!(parse_text_level("cards: Key\ncards_on_hand: 1\n\n@.C\n@.\n").is_err());
+	}
+
+	#[test]
+	fn only_explained_cards_can_be_explained_on_startup() {
+		let v1 = "{ \"map\": [[1, 3]], \"cards\": [\"Key\"], \"cards_on_hand\": 1, \"explain_on_startup\": \"Armor\" }";
+		let error = parse_level(v1).unwrap_err().to_string();
+		assert_eq!(error, "explain_on_startup: there is no explanation for Armor");
+
+		let v2 = "{ \"version\": 2, \"width\": 2, \"height\": 1, \"legend\": { \"@\": \"Player\", \"C\": \"Chest\" }, \"map\": [\"@C\"], \"cards\": [\"Key\"], \"cards_on_hand\": 1, \"explain_on_startup\": \"Health\" }";
+		assert_eq!(parse_level(v2).unwrap_err().to_string(), "explain_on_startup: there is no explanation for Health");
+		assert!(parse_level(&v2.replace("Health", "Attack Line 2")).is_ok());
+
+		let text = "cards: Key\ncards_on_hand: 1\nexplain_on_startup: Armor\n\n@C\n";
+		assert_eq!(parse_text_level(text).unwrap_err().to_string(), "line 3: explain_on_startup: there is no explanation for Armor");
+	}
+
+	#[test]
+	fn v1_rejects_unknown_fields() {
+		let text = "{ \"map\": [[1, 3]], \"cards\": [\"Key\"], \"cards_on_hand\": 1, \"explain_on_startup\": \"\", \"hand\": 2 }";
+		assert!(parse_level(text).is_err());
+	}
+}
@@ -0,0 +1,155 @@
+use ggez::{
+	graphics::*,
+	glam::*,
+	input::keyboard::{KeyCode, KeyInput},
+	input::mouse::MouseButton,
+	Context, GameResult
+};
+
+use crate::global_constants as GlobConst;
+use crate::pack::Pack;
+use crate::save_game::{SaveGame, level_key};
+
+
+const PACKS_POS: Vec2 = vec2(100.0, 160.0);
+const LEVELS_POS: Vec2 = vec2(600.0, 160.0);
+const ROW_HEIGHT: f32 = 40.0;
+const ROW_WIDTH: f32 = 800.0;
+
+
+// what the game has to do after something was picked in the level select
+pub enum LevelSelectAction {
+	Nothing,
+	Play { pack: usize, level: usize },
+}
+
+// lists the discovered packs and their levels, finished levels get a mark
+pub struct LevelSelect {
+	pub packs: Vec<Pack>,
+	pack_idx: usize,
+	level_idx: usize,
+}
+
+impl LevelSelect {
+	pub fn new(packs: Vec<Pack>) -> LevelSelect {
+		LevelSelect {
+			packs,
+			pack_idx: 0,
+			level_idx: 0,
+		}
+	}
+
+	// jumps to a level, e.g. the one that was just played
+	pub fn select(self: &mut LevelSelect, pack: usize, level: usize) {
+		if pack < self.packs.len() {
+			self.pack_idx = pack;
+			self.level_idx = level.min(self.packs[pack].levels.len().saturating_sub(1));
+		}
+	}
+
+	// (pack, level) of the level with the given save game key
+	pub fn find_key(self: &LevelSelect, key: &str) -> Option<(usize, usize)> {
+		self.packs.iter()
+			.enumerate()
+			.find_map(|(p, pack)| (0..pack.levels.len()).find(|l| level_key(pack.level_path(*l)) == key).map(|l| (p, l)))
+	}
+
+	// jumps to the level with the given save game key, if it is in one of the packs
+	pub fn select_key(self: &mut LevelSelect, key: &str) {
+		if let Some((pack, level)) = self.find_key(key) {
+			self.select(pack, level);
+		}
+	}
+
+	// a click on a level plays it
+	pub fn update(self: &mut LevelSelect, ctx: &mut Context) -> GameResult<LevelSelectAction> {
+		if !ctx.mouse.button_just_pressed(MouseButton::Left) {
+			return Ok(LevelSelectAction::Nothing);
+		}
+
+		let mp: Vec2 = ctx.mouse.position().into();
+
+		for i in 0..self.packs.len() {
+			if Rect::new(PACKS_POS.x, PACKS_POS.y + ROW_HEIGHT * i as f32, LEVELS_POS.x - PACKS_POS.x, ROW_HEIGHT).contains(mp) {
+				self.select(i, 0);
+				return Ok(LevelSelectAction::Nothing);
+			}
+		}
+
+		for i in 0..self.level_count() {
+			if Rect::new(LEVELS_POS.x, LEVELS_POS.y + ROW_HEIGHT * i as f32, ROW_WIDTH, ROW_HEIGHT).contains(mp) {
+				self.level_idx = i;
+				return Ok(LevelSelectAction::Play { pack: self.pack_idx, level: i });
+			}
+		}
+
+		Ok(LevelSelectAction::Nothing)
+	}
+
+	pub fn key_down(self: &mut LevelSelect, input: KeyInput) -> LevelSelectAction {
+		if self.packs.is_empty() {
+			return LevelSelectAction::Nothing;
+		}
+
+		match input.keycode {
+			Some(KeyCode::Left) => self.select((self.pack_idx + self.packs.len() - 1) % self.packs.len(), 0),
+			Some(KeyCode::Right) => self.select((self.pack_idx + 1) % self.packs.len(), 0),
+			Some(KeyCode::Up) => self.level_idx = self.level_idx.saturating_sub(1),
+			Some(KeyCode::Down) => self.level_idx = (self.level_idx + 1).min(self.level_count().saturating_sub(1)),
+			Some(KeyCode::Return) | Some(KeyCode::Space) => return LevelSelectAction::Play { pack: self.pack_idx, level: self.level_idx },
+			_ => (),
+		}
+
+		LevelSelectAction::Nothing
+	}
+
+	fn level_count(self: &LevelSelect) -> usize {
+		self.packs.get(self.pack_idx).map_or(0, |pack| pack.levels.len())
+	}
+
+	// finished levels and best scores come from the save game
+	pub fn draw(self: &LevelSelect, ctx: &mut Context, canvas: &mut Canvas, save: &SaveGame) -> GameResult {
+		let mut title = Text::new("LEVEL SELECT");
+		title.set_scale(16.0 * GlobConst::SCALE);
+		canvas.draw(&title, DrawParam::default().dest(vec2(PACKS_POS.x, PACKS_POS.y - 120.0)).z(2000));
+
+		if self.packs.is_empty() {
+			let text = Text::new("NO LEVEL PACKS FOUND");
+			canvas.draw(&text, DrawParam::default().dest(PACKS_POS).z(2000));
+			return Ok(());
+		}
+
+		for (i, pack) in self.packs.iter().enumerate() {
+			let marker = if i == self.pack_idx { "> " } else { "  " };
+			let finished = (0..pack.levels.len()).filter(|l| save.is_completed(pack.level_path(*l))).count();
+
+			let text = Text::new(format!("{}{} ({}/{})", marker, pack.name.to_uppercase(), finished, pack.levels.len()));
+			canvas.draw(&text, DrawParam::default().dest(PACKS_POS + vec2(0.0, ROW_HEIGHT * i as f32)).z(2000));
+		}
+
+		let pack = &self.packs[self.pack_idx];
+
+		for (i, level) in pack.levels.iter().enumerate() {
+			let marker = if i == self.level_idx { "> " } else { "  " };
+			let mark = if save.is_completed(pack.level_path(i)) { "[X]" } else { "[ ]" };
+			let par = level.par.map_or(String::new(), |par| format!("  PAR {}", par));
+			let best = save.best_score(pack.level_path(i)).map_or(String::new(), |best| format!("  BEST {}", best));
+
+			let text = Text::new(format!("{}{} {}. {}{}{}", marker, mark, i + 1, level.title.to_uppercase(), par, best));
+			canvas.draw(&text, DrawParam::default().dest(LEVELS_POS + vec2(0.0, ROW_HEIGHT * i as f32)).z(2000));
+		}
+
+		let (_, screen_h) = ctx.gfx.drawable_size();
+
+		let mut footer = String::new();
+		if !pack.author.is_empty() {
+			footer += &format!("BY {}\n", pack.author.to_uppercase());
+		}
+		footer += "LEFT, RIGHT: PACK   UP, DOWN: LEVEL   ENTER: PLAY";
+
+		let text = Text::new(footer);
+		canvas.draw(&text, DrawParam::default().dest(vec2(PACKS_POS.x, screen_h - 100.0)).z(2000));
+
+		Ok(())
+	}
+}
@@ -0,0 +1,41 @@
+// module declarations, the ones drawing with ggez only build with the graphics feature
+#[cfg(feature = "graphics")]
+pub mod global_constants;
+#[cfg(feature = "graphics")]
+pub mod grid_drawer;
+#[cfg(feature = "graphics")]
+pub mod player;
+#[cfg(feature = "graphics")]
+pub mod card;
+pub mod math;
+pub mod level_manager;
+#[cfg(feature = "graphics")]
+pub mod explainer;
+pub mod rules;
+pub mod grid;
+pub mod campaign;
+pub mod solver;
+pub mod validator;
+pub mod generator;
+#[cfg(feature = "graphics")]
+pub mod editor;
+pub mod tiled;
+pub mod pack;
+#[cfg(feature = "graphics")]
+pub mod level_select;
+#[cfg(feature = "graphics")]
+pub mod scene;
+#[cfg(feature = "graphics")]
+pub mod screens;
+pub mod save_game;
+pub mod snapshot;
+pub mod history;
+pub mod replay;
+pub mod enemy;
+#[cfg(feature = "graphics")]
+pub mod enemy_drawer;
+pub mod breakable;
+#[cfg(feature = "graphics")]
+pub mod breakable_drawer;
+#[cfg(feature = "graphics")]
+pub mod level_loader;
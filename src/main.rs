@@ -14,49 +14,95 @@ use ggez::{
 	Context, ContextBuilder, GameResult, GameError
 };
 use std::f32::consts::PI as PI;
+use std::path::{Path, PathBuf};
 
 extern crate sdl2;
 use sdl2::mouse::Cursor;
 use sdl2::surface::Surface;
 use sdl2::pixels::PixelFormatEnum;
 
-// module declarations
-pub mod global_constants;
-pub mod grid_drawer;
-pub mod player;
-pub mod card;
-pub mod math;
-pub mod level_manager;
-pub mod explainer;
-
 // module imports
-use global_constants as GlobConst;
-use grid_drawer as GridDrawer;
-use level_manager as LevelManager;
-use player::*;
-use card::*;
-use explainer::*;
+use nordic_grid::global_constants as GlobConst;
+use nordic_grid::grid_drawer as GridDrawer;
+use nordic_grid::level_manager as LevelManager;
+use nordic_grid::level_manager::Level;
+use nordic_grid::level_loader as LevelLoader;
+use nordic_grid::player::*;
+use nordic_grid::card::*;
+use nordic_grid::explainer::*;
+use nordic_grid::rules::{Rules, Outcome, Action};
+use nordic_grid::campaign::Campaign;
+use nordic_grid::editor::{Editor, EditorAction};
+use nordic_grid::level_select::{LevelSelect, LevelSelectAction};
+use nordic_grid::pack::{self as Pack, PACKS_DIR};
+use nordic_grid::scene::{Scene, SceneChange, SceneStack};
+use nordic_grid::screens::{ScreenAction, TitleScreen, PauseScreen, LevelCompleteScreen, GameOverScreen};
+use nordic_grid::save_game::{self, SaveGame, SAVE_FILE};
+use nordic_grid::snapshot::{self, Snapshot};
+use nordic_grid::history::History;
+use nordic_grid::replay::{self, Recorder, Playback};
+use nordic_grid::enemy_drawer::EnemyDrawer;
+use nordic_grid::breakable_drawer::BreakableDrawer;
 
 
 const TWO_PI: f32 = 2.0 * PI;
+const LEVEL_TRANSITION_TIME: f32 = 1.5;		// seconds between opening the chest and the summary
+const RESTART_BUTTON_POS: Vec2 = vec2(20.0, 20.0);
+const UNDO_BUTTON_POS: Vec2 = vec2(180.0, 20.0);
+const REDO_BUTTON_POS: Vec2 = vec2(360.0, 20.0);
+const END_TURN_BUTTON_POS: Vec2 = vec2(520.0, 20.0);		// only in levels with energy
+const NOTICE_TIME: f32 = 2.0;				// seconds a notice like "SNAPSHOT SAVED" stays
 
 
 
 // GAME STATE STRUCT
 struct Game {
 	quad_mesh: Mesh,		// temporary
+	rules: Rules,
+	history: History,				// undo and redo in the current level
+	recorder: Option<Recorder>,		// None if the level didn't start from the beginning
+	playback: Option<Playback>,		// Some while a replay is watched
 	player: Player,
+	enemy_drawer: EnemyDrawer,
+	breakable_drawer: BreakableDrawer,
 	cards: Vec<Card>,
 	explainer: Explainer,
 	tile_images: Vec<Image>,	// make a struct in grid drawer?
+	scenes: SceneStack,
+	campaign: Option<Campaign>,		// None outside of levels
+	queued_campaign: Option<Campaign>,	// started once the screen faded to black
+	level_select: LevelSelect,
+	level_complete: Option<LevelCompleteScreen>,
+	game_over: Option<GameOverScreen>,
+	save: SaveGame,
+	save_error: Option<String>,		// the save on disk couldn't be read, it is never overwritten then
+	save_path: PathBuf,
+	snapshot_path: PathBuf,			// F5 writes the level as it is, F9 restores it
+	notice: Option<(String, f32)>,	// a short message and how long it stays
+	show_hint: bool,
+	level: Option<Level>,			// kept around to restart it
+	seed_override: Option<u64>,		// from the command line, used for every level
+	level_transition: Option<f32>,	// time left until the level complete summary shows
+	restart_text: Text,
+	redo_text: Text,
+	end_turn_text: Text,
+	editor: Option<Editor>,			// Some while the current level is edited
 }
 
 impl Game {
-	pub fn new(ctx: &mut Context) -> Game {
+	pub fn new(ctx: &mut Context, level_select: LevelSelect, seed_override: Option<u64>, save_path: PathBuf, snapshot_path: PathBuf) -> Game {
 		let player = Player::new(ctx, 0, 0);
-		Game {
+		let (save, save_error) = save_game::read_save_or_default(&save_path);
+
+		let mut game = Game {
 			quad_mesh: Mesh::new_rectangle(ctx, DrawMode::fill(), Rect::new(0.0, 0.0, GlobConst::QUAD_SIZE, GlobConst::QUAD_SIZE), Color::WHITE).unwrap(),
-			player: player,
+			rules: Rules::new(),
+			history: History::new(None),
+			recorder: None,
+			playback: None,
+			player,
+			enemy_drawer: EnemyDrawer::new(ctx),
+			breakable_drawer: BreakableDrawer::new(ctx),
 			cards: Vec::<Card>::new(),
 			explainer: Explainer::new(ctx),
 			tile_images: vec!(
@@ -64,6 +110,52 @@ impl Game {
 				Image::from_path(ctx, "/tree.png").unwrap(),
 				Image::from_path(ctx, "/chest.png").unwrap()
 			),
+			scenes: SceneStack::new(Scene::Title),
+			campaign: None,
+			queued_campaign: None,
+			level_select,
+			level_complete: None,
+			game_over: None,
+			save,
+			save_error: save_error.map(|e| e.to_string()),
+			save_path,
+			snapshot_path,
+			notice: None,
+			show_hint: false,
+			level: None,
+			seed_override,
+			level_transition: None,
+			restart_text: Text::new("RESTART (R)"),
+			redo_text: Text::new("REDO (Y)"),
+			end_turn_text: Text::new("END TURN (SPACE)"),
+			editor: None,
+		};
+
+		// tutorials that were seen in an earlier run aren't shown again
+		for card_type in game.save.explained.clone() {
+			game.explainer.mark_explained(card_type);
+		}
+
+		if let Some(key) = &game.save.last_level {
+			game.level_select.select_key(key);
+		}
+
+		game.scenes.fades = game.save.settings.fades;
+
+		game
+	}
+
+	// errors are only reported, losing the save is better than crashing the game
+	// a save that couldn't be read at the start isn't touched, the title screen tells why
+	fn save(self: &mut Game) {
+		self.save.explained = self.explainer.explained();
+
+		if self.save_error.is_some() {
+			return;
+		}
+
+		if let Err(e) = save_game::write_save(&self.save_path, &self.save) {
+			eprintln!("couldn't write the save game {}: {}", self.save_path.display(), e);
 		}
 	}
 }
@@ -73,7 +165,196 @@ impl EventHandler for Game {
 
 		let dt = ctx.time.delta().as_secs_f32();
 
-		self.player.update(ctx, &dt)?;
+		if let Some(change) = self.scenes.update(dt) {
+			self.scene_changed(ctx, change)?;
+		}
+
+		// nothing reacts while the screen fades
+		if self.scenes.is_fading() {
+			return Ok(());
+		}
+
+		match self.scenes.top() {
+			Scene::LevelSelect => {
+				if let LevelSelectAction::Play { pack, level } = self.level_select.update(ctx)? {
+					self.play_from_level_select(pack, level);
+				}
+			},
+			Scene::Playing => self.update_playing(ctx, dt)?,
+			Scene::Replay => self.update_replay(ctx, dt)?,
+			Scene::Editor => {
+				if let Some(editor) = &mut self.editor {
+					editor.update(ctx)?;
+				}
+			},
+			_ => (),
+		}
+
+		Ok(())
+	}
+
+	fn draw(self: &mut Game, ctx: &mut Context) -> GameResult {
+
+		// get canvas, disable filtering, and dimensions
+		let mut canvas = Canvas::from_frame(ctx, Color::from_rgb(30, 30, 45));
+		canvas.set_sampler(Sampler::nearest_clamp());
+
+		// overlays are drawn on top of the scene below them
+		for scene in self.scenes.visible().to_vec() {
+			match scene {
+				Scene::Title => TitleScreen.draw(ctx, &mut canvas, &self.save.settings, self.save_error.as_deref())?,
+				Scene::LevelSelect => self.level_select.draw(ctx, &mut canvas, &self.save)?,
+				Scene::Playing => self.draw_playing(ctx, &mut canvas)?,
+				Scene::Replay => {
+					self.draw_playing(ctx, &mut canvas)?;
+					self.draw_playback(ctx, &mut canvas)?;
+				},
+				Scene::Editor => {
+					if let Some(editor) = &mut self.editor {
+						editor.draw(ctx, &mut canvas, &self.tile_images)?;
+					}
+				},
+				Scene::Paused => PauseScreen.draw(ctx, &mut canvas)?,
+				Scene::LevelComplete => {
+					if let Some(screen) = &self.level_complete {
+						screen.draw(ctx, &mut canvas)?;
+					}
+				},
+				Scene::GameOver => {
+					if let Some(screen) = &self.game_over {
+						screen.draw(ctx, &mut canvas)?;
+					}
+				},
+			}
+		}
+
+		self.scenes.draw_fade(ctx, &mut canvas)?;
+
+		// present
+		canvas.finish(ctx)?;
+		Ok(())
+	}
+
+	fn key_down_event(self: &mut Game, ctx: &mut Context, input: KeyInput, repeat: bool) -> GameResult {
+		if self.scenes.is_fading() {
+			return Ok(());
+		}
+
+		let action = match self.scenes.top() {
+			Scene::Title => TitleScreen.key_down(input),
+			Scene::LevelSelect => {
+				if input.keycode == Some(KeyCode::Escape) {
+					ScreenAction::Title
+				} else {
+					if let LevelSelectAction::Play { pack, level } = self.level_select.key_down(input) {
+						self.play_from_level_select(pack, level);
+					}
+					ScreenAction::Nothing
+				}
+			},
+			Scene::Playing => {
+				self.key_down_playing(ctx, input, repeat)?;
+				ScreenAction::Nothing
+			},
+			Scene::Replay => {
+				self.key_down_replay(ctx, input)?;
+				ScreenAction::Nothing
+			},
+			Scene::Editor => {
+				if let Some(EditorAction::PlayTest) = self.editor.as_mut().map(|editor| editor.key_down(input)) {
+					self.play_test(ctx);
+				}
+				ScreenAction::Nothing
+			},
+			Scene::Paused => PauseScreen.key_down(input),
+			Scene::LevelComplete => self.level_complete.as_ref().map_or(ScreenAction::Nothing, |screen| screen.key_down(input)),
+			Scene::GameOver => self.game_over.as_ref().map_or(ScreenAction::Nothing, |screen| screen.key_down(input)),
+		};
+
+		self.screen_action(ctx, action);
+
+		Ok(())
+	}
+
+	fn quit_event(self: &mut Game, ctx: &mut Context) -> GameResult<bool> {
+		self.save();
+
+		Ok(false)
+	}
+}
+
+impl Game {
+	fn update_playing(self: &mut Game, ctx: &mut Context, dt: f32) -> GameResult {
+		self.update_notice(dt);
+
+		if let Some(recorder) = &mut self.recorder {
+			recorder.tick(dt);
+		}
+
+		self.enemy_drawer.update(dt, &self.rules);
+		self.breakable_drawer.update(dt);
+
+		if let Some(target) = self.player.update(ctx, &dt, &self.rules)? {
+			if let Some(Outcome::ChestOpened(_)) = self.perform(ctx, Action::ChooseTarget(target)) {
+				self.finish_level();
+				self.level_transition = Some(LEVEL_TRANSITION_TIME);
+			}
+		}
+
+		// wait a bit after the chest opened, then show the summary
+		if let Some(time_left) = self.level_transition {
+			if time_left > dt {
+				self.level_transition = Some(time_left - dt);
+				return Ok(());
+			}
+
+			self.level_transition = None;
+			if self.level_complete.is_some() {
+				self.scenes.apply(SceneChange::Push(Scene::LevelComplete));
+			} else {
+				// a restored level that isn't part of any pack
+				self.scenes.fade_to(SceneChange::Reset(Scene::LevelSelect));
+			}
+			return Ok(());
+		}
+
+		if self.rules.is_defeated() {
+			self.game_over = Some(GameOverScreen { reason: "you were defeated".to_string(), can_undo: self.history.can_undo() });
+			self.scenes.apply(SceneChange::Push(Scene::GameOver));
+			return Ok(());
+		}
+
+		if let Some(limit) = self.rules.turn_limit.filter(|_| self.rules.is_out_of_turns()) {
+			self.game_over = Some(GameOverScreen { reason: format!("the chest wasn't opened within {} turns", limit), can_undo: self.history.can_undo() });
+			self.scenes.apply(SceneChange::Push(Scene::GameOver));
+			return Ok(());
+		}
+
+		if self.rules.is_stuck() {
+			self.game_over = Some(GameOverScreen { reason: "no card can be played anymore".to_string(), can_undo: self.history.can_undo() });
+			self.scenes.apply(SceneChange::Push(Scene::GameOver));
+			return Ok(());
+		}
+
+		if ctx.mouse.button_just_pressed(MouseButton::Left) {
+			let mp = ctx.mouse.position();
+
+			if self.restart_button_rect(ctx)?.contains(mp) {
+				return self.restart_level(ctx);
+			}
+			if button_rect(ctx, &self.undo_text(), UNDO_BUTTON_POS)?.contains(mp) {
+				self.undo(ctx);
+				return Ok(());
+			}
+			if button_rect(ctx, &self.redo_text, REDO_BUTTON_POS)?.contains(mp) {
+				self.redo(ctx);
+				return Ok(());
+			}
+			if self.rules.uses_energy() && button_rect(ctx, &self.end_turn_text, END_TURN_BUTTON_POS)?.contains(mp) {
+				self.end_turn(ctx);
+				return Ok(());
+			}
+		}
 
 		let len = self.cards.len() as u8;
 		let mp = ctx.mouse.position();
@@ -82,22 +363,22 @@ impl EventHandler for Game {
 		// highlight card
 		for (i, card) in self.cards.iter_mut().enumerate() {
 			card.update(ctx, len, i as u8, &dt)?;
-			
+
 			card.target_rotation = 0.0;
 			card.selected = false;
 			if card.mouse_over(mp.x, mp.y) {
 				selected_i = i as i16;
 			}
 		}
-		
+
 		// else selected_i is -1
 		if selected_i >= 0 {
 			self.cards[selected_i as usize].selected = true;
 			self.cards[selected_i as usize].pos_rel_to_selected = 0.0;
 			self.cards[selected_i as usize].target_rotation = TWO_PI / -64.0;
-			
+
 			if ctx.mouse.button_just_pressed(MouseButton::Left) {
-				self.use_card_at(selected_i as usize)?;
+				self.use_card_at(ctx, selected_i as usize)?;
 			}
 
 			for (i, card) in self.cards.iter_mut().enumerate() {
@@ -114,64 +395,588 @@ impl EventHandler for Game {
 		Ok(())
 	}
 
-	fn draw(self: &mut Game, ctx: &mut Context) -> GameResult {
+	fn update_notice(self: &mut Game, dt: f32) {
+		if let Some((_, time_left)) = &mut self.notice {
+			*time_left -= dt;
+			if *time_left <= 0.0 {
+				self.notice = None;
+			}
+		}
+	}
 
-		// get canvas, disable filtering, and dimensions
-		let mut canvas = Canvas::from_frame(ctx, Color::from_rgb(30, 30, 45));
-		canvas.set_sampler(Sampler::nearest_clamp());
+	// the player and the cards only animate, every action comes from the replay
+	fn update_replay(self: &mut Game, ctx: &mut Context, dt: f32) -> GameResult {
+		self.update_notice(dt);
+
+		self.player.update(ctx, &dt, &self.rules)?;
+		self.enemy_drawer.update(dt, &self.rules);
+		self.breakable_drawer.update(dt);
+
+		let len = self.cards.len() as u8;
+		for (i, card) in self.cards.iter_mut().enumerate() {
+			card.update(ctx, len, i as u8, &dt)?;
+		}
+
+		if let Some(action) = self.playback.as_mut().and_then(|playback| playback.update(dt)) {
+			self.play_back(ctx, action);
+		}
+
+		Ok(())
+	}
+
+	fn draw_playing(self: &mut Game, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
 		let (screen_w, screen_h) = ctx.gfx.size();
-		
+
 		// draw grid with (temporary) quad mesh
-		GridDrawer::draw_grid(ctx, &mut canvas, &self.tile_images)?;
+		GridDrawer::draw_grid(ctx, canvas, &self.tile_images, &self.rules.grid)?;
 
 		// draw player
-		self.player.draw(ctx, &mut canvas, &self.quad_mesh)?;
+		self.player.draw(ctx, canvas, &self.quad_mesh, &self.rules)?;
+
+		self.enemy_drawer.draw(ctx, canvas, &self.rules)?;
+		self.breakable_drawer.draw(ctx, canvas, &self.rules)?;
 
 		// draw cards
 		for card in &mut self.cards {
-			card.draw(ctx, &mut canvas)?;
+			card.draw(ctx, canvas)?;
 		}
 
-		self.explainer.draw(ctx, &mut canvas)?;
+		self.explainer.draw(ctx, canvas)?;
+
+		self.draw_progress(ctx, canvas)?;
+
+		self.draw_level_info(ctx, canvas)?;
+
+		canvas.draw(&self.restart_text, DrawParam::default().dest(RESTART_BUTTON_POS).z(2000));
+
+		// undo and redo are grayed out when there is nothing to take back
+		let button_color = |active: bool| if active { Color::WHITE } else { Color::new(1.0, 1.0, 1.0, 0.3) };
+		canvas.draw(&self.undo_text(), DrawParam::default().dest(UNDO_BUTTON_POS).color(button_color(self.history.can_undo())).z(2000));
+		canvas.draw(&self.redo_text, DrawParam::default().dest(REDO_BUTTON_POS).color(button_color(self.history.can_redo())).z(2000));
+
+		if self.rules.uses_energy() {
+			canvas.draw(&self.end_turn_text, DrawParam::default().dest(END_TURN_BUTTON_POS).color(button_color(self.rules.can_end_turn())).z(2000));
+		}
+
+		// show the seed of shuffled levels so the draw order can be reproduced
+		if self.level.as_ref().is_some_and(|level| level.shuffle) {
+			let seed_text = Text::new(format!("SEED: {}", self.rules.seed));
+			canvas.draw(&seed_text, DrawParam::default().dest(RESTART_BUTTON_POS + vec2(0.0, 24.0)).z(2000));
+		}
+
+		if let Some((notice, _)) = &self.notice {
+			let notice_text = Text::new(notice.as_str());
+			canvas.draw(&notice_text, DrawParam::default().dest(RESTART_BUTTON_POS + vec2(0.0, 48.0)).z(2000));
+		}
+
+		let piles_text = Text::new(format!("DRAW PILE: {}\nDISCARD PILE: {}", self.rules.draw_pile.len(), self.rules.discard_pile.len()));
+		canvas.draw(&piles_text, DrawParam::default().dest(vec2(screen_w - 180.0, screen_h - 60.0)).z(2000));
+
+		let mut turn = match self.rules.turn_limit {
+			Some(limit) => format!("TURN: {}/{}", self.rules.turn, limit),
+			None => format!("TURN: {}", self.rules.turn),
+		};
+		if let Some(energy) = self.rules.energy_per_turn {
+			turn += &format!("\nENERGY: {}/{}", self.rules.energy, energy);
+		}
+		let turn_text = Text::new(turn);
+		canvas.draw(&turn_text, DrawParam::default().dest(vec2(screen_w - 180.0, screen_h - 110.0)).z(2000));
 
-		// present
-		canvas.finish(ctx)?;
 		Ok(())
 	}
 
-	fn key_down_event(self: &mut Game, ctx: &mut Context, input: KeyInput, repeat: bool) -> GameResult {
+	fn key_down_playing(self: &mut Game, ctx: &mut Context, input: KeyInput, repeat: bool) -> GameResult {
 		// call the players keydown function to make him move
-		self.player.key_down(ctx, input, repeat)?;
+		self.player.key_down(ctx, input, repeat, &self.rules.grid)?;
+
+		match input.keycode {
+			Some(KeyCode::R) => self.restart_level(ctx)?,
+			Some(KeyCode::C) => self.explainer.close(),
+			Some(KeyCode::E) => self.open_editor(ctx),
+			Some(KeyCode::H) => self.show_hint = !self.show_hint,
+			Some(KeyCode::V) if self.level_transition.is_none() => self.start_playback(ctx),
+			Some(KeyCode::Z) => self.undo(ctx),
+			Some(KeyCode::Y) => self.redo(ctx),
+			Some(KeyCode::Space) => self.end_turn(ctx),
+			Some(KeyCode::F5) if self.level_transition.is_none() => self.write_snapshot(),
+			Some(KeyCode::F9) if self.level_transition.is_none() => {
+				let path = self.snapshot_path.clone();
+				if let Err(e) = self.restore_snapshot(ctx, &path) {
+					self.notice = Some((format!("COULDN'T RESTORE THE SNAPSHOT: {}", e), NOTICE_TIME));
+				}
+			},
+			Some(KeyCode::L) if self.level_transition.is_none() => self.scenes.fade_to(SceneChange::Reset(Scene::LevelSelect)),
+			Some(KeyCode::Escape) | Some(KeyCode::P) if self.level_transition.is_none() => self.scenes.apply(SceneChange::Push(Scene::Paused)),
+			_ => (),
+		}
 
 		Ok(())
 	}
 
-	
-}
+	fn key_down_replay(self: &mut Game, ctx: &mut Context, input: KeyInput) -> GameResult {
+		let Some(playback) = &mut self.playback else {
+			return Ok(());
+		};
 
-impl Game {
-	fn use_card_at(self: &mut Game, idx: usize) -> GameResult {
-		let card = &self.cards[idx];
-		
-		match &card.card_type {
-			&CardType::Move => {
-				self.player.move_player();
+		match input.keycode {
+			Some(KeyCode::Space) => playback.paused = !playback.paused,
+			Some(KeyCode::Up) => playback.faster(),
+			Some(KeyCode::Down) => playback.slower(),
+			Some(KeyCode::Right) | Some(KeyCode::S) => {
+				if let Some(action) = playback.step() {
+					self.play_back(ctx, action);
+				}
+			},
+			Some(KeyCode::Escape) | Some(KeyCode::V) => self.stop_playback(ctx)?,
+			_ => (),
+		}
+
+		Ok(())
+	}
+
+	// the menu screens only say what they want, the game does it
+	fn screen_action(self: &mut Game, ctx: &mut Context, action: ScreenAction) {
+		match action {
+			ScreenAction::Nothing => (),
+			ScreenAction::Start | ScreenAction::LevelSelect => self.scenes.fade_to(SceneChange::Reset(Scene::LevelSelect)),
+			ScreenAction::Resume => self.scenes.apply(SceneChange::Pop),
+			ScreenAction::Restart => self.scenes.fade_to(SceneChange::Reset(Scene::Playing)),
+			ScreenAction::NextLevel => {
+				self.queued_campaign = self.campaign.as_ref().and_then(Campaign::next_level);
+				self.scenes.fade_to(SceneChange::Reset(Scene::Playing));
 			},
-			&CardType::Health => {
-				self.player.change_at(ValueType::Health, 1.0)?;
+			ScreenAction::Title => self.scenes.fade_to(SceneChange::Reset(Scene::Title)),
+			ScreenAction::Quit => ctx.request_quit(),
+			ScreenAction::ToggleFades => {
+				self.save.settings.fades = !self.save.settings.fades;
+				self.scenes.fades = self.save.settings.fades;
+				self.save();
 			},
-			&CardType::Armor => {
-				self.player.change_at(ValueType::Armor, 1.0)?;
+			ScreenAction::Undo => {
+				self.scenes.apply(SceneChange::Pop);
+				self.undo(ctx);
 			},
-			&CardType::Key => {
-				self.player.use_key();
+			ScreenAction::ToggleHints => {
+				self.save.settings.show_hints = !self.save.settings.show_hints;
+				self.save();
 			},
 		}
+	}
 
-		self.cards.remove(idx);
+	// called once the screen is black and the scene changed
+	fn scene_changed(self: &mut Game, ctx: &mut Context, change: SceneChange) -> GameResult {
+		match change {
+			SceneChange::Reset(Scene::Playing) => {
+				self.level_complete = None;
+				self.game_over = None;
+
+				// a new level was picked, everything else plays the current level again
+				match self.queued_campaign.take() {
+					Some(campaign) => {
+						self.campaign = Some(campaign);
+						self.load_current_level(ctx)?;
+					},
+					None => self.restart_level(ctx)?,
+				}
+			},
+			SceneChange::Reset(Scene::LevelSelect) | SceneChange::Reset(Scene::Title) => self.leave_level(),
+			_ => (),
+		}
 
 		Ok(())
 	}
+
+	fn play_from_level_select(self: &mut Game, pack: usize, level: usize) {
+		let Some(pack) = self.level_select.packs.get(pack) else {
+			return;
+		};
+
+		self.queued_campaign = Some(Campaign::new(pack.clone(), level));
+		self.scenes.fade_to(SceneChange::Reset(Scene::Playing));
+	}
+
+	// throws the level away, the level select starts at the level that was played
+	fn leave_level(self: &mut Game) {
+		self.save();
+
+		if let Some(campaign) = self.campaign.take() {
+			let pack = self.level_select.packs.iter().position(|pack| pack.dir == campaign.pack.dir).unwrap_or(0);
+			self.level_select.select(pack, campaign.current_index());
+		}
+
+		LevelLoader::unload_level(&mut self.rules, &mut self.cards, &mut self.explainer);
+		self.enemy_drawer.clear();
+		self.breakable_drawer.clear();
+		self.level = None;
+		self.level_transition = None;
+		self.level_complete = None;
+		self.game_over = None;
+		self.editor = None;
+	}
+
+	// saves the score right away and prepares the summary that is shown after the chest opened
+	fn finish_level(self: &mut Game) {
+		let Some(campaign) = &self.campaign else {
+			return;
+		};
+
+		let level = campaign.current_level();
+		let cards_played = self.rules.discard_pile.len();
+		let best = self.save.best_score(campaign.current_level_path());
+		let new_best = self.save.complete_level(campaign.current_level_path(), cards_played as u32);
+
+		self.level_complete = Some(LevelCompleteScreen {
+			title: level.title.clone(),
+			cards_played,
+			par: level.par,
+			best,
+			new_best,
+			last_level: campaign.is_last_level(),
+		});
+
+		self.write_replay();
+		self.save();
+	}
+
+	// the replay of the last finished play is kept next to the level file
+	fn write_replay(self: &Game) {
+		let (Some(recorder), Some(campaign), Some(level)) = (&self.recorder, &self.campaign, &self.level) else {
+			return;
+		};
+
+		// an edited level that wasn't saved would get a replay that doesn't fit the file
+		let level_path = campaign.current_level_path();
+		match LevelManager::read_level(&level_path) {
+			Ok(on_disk) if LevelManager::level_to_json(&on_disk) == LevelManager::level_to_json(level) => (),
+			_ => return,
+		}
+
+		let path = replay::replay_path(&level_path);
+		if let Err(e) = replay::write_replay(&path, recorder.replay()) {
+			eprintln!("couldn't write the replay {}: {}", path.display(), e);
+		}
+	}
+
+	// watches the replay of the current level from the start
+	fn start_playback(self: &mut Game, ctx: &mut Context) {
+		let (Some(campaign), Some(level)) = (&self.campaign, &self.level) else {
+			return;
+		};
+
+		let path = replay::replay_path(campaign.current_level_path());
+		let replay = match replay::read_replay(&path) {
+			Ok(replay) => replay,
+			Err(e) => {
+				self.notice = Some((format!("NO REPLAY FOR THIS LEVEL: {}", e), NOTICE_TIME));
+				return;
+			},
+		};
+
+		// a shuffled level with a fixed seed would deal other cards than the ones that were recorded
+		let playback = Playback::new(replay);
+		if let Some(seed) = replay::required_seed(level, self.seed_override).filter(|seed| *seed != playback.seed()) {
+			self.notice = Some((format!("THE REPLAY WAS RECORDED WITH SEED {}, BUT THE LEVEL DEALS WITH SEED {}", playback.seed(), seed), NOTICE_TIME));
+			return;
+		}
+
+		LevelLoader::start_level(ctx, level, playback.seed(), &mut self.rules, &mut self.cards, &mut self.player, &mut self.explainer);
+		self.history = History::new(None);
+		self.recorder = None;
+		self.enemy_drawer.clear();
+		self.breakable_drawer.clear();
+		self.playback = Some(playback);
+		self.scenes.apply(SceneChange::Push(Scene::Replay));
+	}
+
+	// back to playing the level from the start
+	fn stop_playback(self: &mut Game, ctx: &mut Context) -> GameResult {
+		self.playback = None;
+		self.scenes.apply(SceneChange::Pop);
+
+		self.restart_level(ctx)
+	}
+
+	// a replay of an older version of the level can contain steps that aren't possible anymore
+	fn play_back(self: &mut Game, ctx: &mut Context, action: Action) {
+		match self.perform(ctx, action) {
+			Some(Outcome::ChestOpened(_)) => self.notice = Some(("THE REPLAY OPENED THE CHEST".to_string(), NOTICE_TIME)),
+			Some(_) => (),
+			None => {
+				if let Some(playback) = &mut self.playback {
+					playback.stop();
+				}
+				self.notice = Some((format!("THE REPLAY DOESN'T FIT THE LEVEL: {:?} ISN'T POSSIBLE", action), NOTICE_TIME));
+			},
+		}
+	}
+
+	fn draw_playback(self: &mut Game, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+		let Some(playback) = &self.playback else {
+			return Ok(());
+		};
+
+		let (step, steps) = playback.progress();
+		let state = if playback.is_finished() { "FINISHED" } else if playback.paused { "PAUSED" } else { "PLAYING" };
+
+		let (_, screen_h) = ctx.gfx.drawable_size();
+
+		let text = Text::new(format!(
+			"REPLAY: STEP {}/{}, SPEED x{}, {}\nSPACE PLAY/PAUSE, RIGHT STEP, UP/DOWN SPEED, ESC BACK",
+			step, steps, playback.speed, state
+		));
+
+		canvas.draw(&text, DrawParam::default().dest(vec2(RESTART_BUTTON_POS.x, screen_h - 60.0)).z(2000));
+
+		Ok(())
+	}
+
+	fn load_current_level(self: &mut Game, ctx: &mut Context) -> GameResult {
+		let Some(campaign) = &self.campaign else {
+			return Ok(());
+		};
+
+		self.show_hint = self.save.settings.show_hints;
+		self.level_transition = None;
+
+		let path = campaign.current_level_path();
+		self.save.last_level = Some(save_game::level_key(&path));
+		let level = LevelLoader::load_level(ctx, &path, self.seed_override, &mut self.rules, &mut self.cards, &mut self.player, &mut self.explainer)
+			.map_err(|e| GameError::CustomError(format!("couldn't load level {}: {}", path.display(), e)))?;
+
+		self.level = Some(level);
+		self.level_started();
+
+		Ok(())
+	}
+
+	// starts the current level again without reading it from disk
+	fn restart_level(self: &mut Game, ctx: &mut Context) -> GameResult {
+		// nothing to restart while the chest opens
+		if self.level_transition.is_some() {
+			return Ok(());
+		}
+
+		// same seed, so the cards come in the same order again
+		if let Some(level) = &self.level {
+			let seed = self.rules.seed;
+			LevelLoader::start_level(ctx, level, seed, &mut self.rules, &mut self.cards, &mut self.player, &mut self.explainer);
+			self.level_started();
+		}
+
+		Ok(())
+	}
+
+	// a fresh start of the level: nothing to undo and a new recording
+	fn level_started(self: &mut Game) {
+		let undo_limit = self.level.as_ref().and_then(|level| level.undo_limit);
+
+		self.history = History::new(undo_limit);
+		self.recorder = Some(Recorder::new(self.rules.seed));
+		self.enemy_drawer.clear();
+		self.breakable_drawer.clear();
+	}
+
+	// edits the level that is currently played, including unsaved edits from the last play-test
+	fn open_editor(self: &mut Game, ctx: &mut Context) {
+		if self.level_transition.is_some() {
+			return;
+		}
+
+		if let (Some(level), Some(campaign)) = (&self.level, &self.campaign) {
+			self.editor = Some(Editor::new(ctx, level.clone(), campaign.current_level_path()));
+			self.scenes.apply(SceneChange::Push(Scene::Editor));
+		}
+	}
+
+	// leaves the editor and plays the edited level from the start
+	fn play_test(self: &mut Game, ctx: &mut Context) {
+		let Some(editor) = self.editor.take() else {
+			return;
+		};
+
+		self.scenes.apply(SceneChange::Pop);
+
+		let seed = editor.level.choose_seed(self.seed_override);
+		LevelLoader::start_level(ctx, &editor.level, seed, &mut self.rules, &mut self.cards, &mut self.player, &mut self.explainer);
+		self.level = Some(editor.level);
+		self.level_started();
+	}
+
+	fn write_snapshot(self: &mut Game) {
+		let Some(level) = &self.level else {
+			return;
+		};
+
+		let level_path = self.campaign.as_ref().map(|campaign| save_game::level_key(campaign.current_level_path()));
+		let snapshot = Snapshot::new(level_path, level, &self.rules);
+
+		let notice = match snapshot::write_snapshot(&self.snapshot_path, &snapshot) {
+			Ok(()) => format!("SNAPSHOT SAVED TO {}", self.snapshot_path.display()),
+			Err(e) => format!("COULDN'T SAVE THE SNAPSHOT: {}", e),
+		};
+		self.notice = Some((notice, NOTICE_TIME));
+	}
+
+	// plays on exactly where the snapshot was taken, in the pack of its level if there is one
+	fn restore_snapshot(self: &mut Game, ctx: &mut Context, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+		let snapshot = snapshot::read_snapshot(path)?;
+
+		if let Some((pack, level)) = snapshot.level_path.as_ref().and_then(|key| self.level_select.find_key(key)) {
+			self.campaign = Some(Campaign::new(self.level_select.packs[pack].clone(), level));
+		}
+
+		LevelLoader::restore(ctx, &snapshot, &mut self.rules, &mut self.cards, &mut self.player, &mut self.explainer);
+		self.history = History::new(snapshot.level.undo_limit);
+		self.recorder = None;		// a replay has to start at the beginning of the level
+		self.enemy_drawer.clear();
+		self.breakable_drawer.clear();
+
+		self.level = Some(snapshot.level);
+		self.level_transition = None;
+		self.level_complete = None;
+		self.game_over = None;
+		self.notice = Some(("SNAPSHOT RESTORED".to_string(), NOTICE_TIME));
+		self.scenes.apply(SceneChange::Reset(Scene::Playing));
+
+		Ok(())
+	}
+
+	fn restart_button_rect(self: &Game, ctx: &mut Context) -> GameResult<Rect> {
+		button_rect(ctx, &self.restart_text, RESTART_BUTTON_POS)
+	}
+
+	// shows how many undos are left in levels with a limit
+	fn undo_text(self: &Game) -> Text {
+		match self.history.undos_left() {
+			Some(left) => Text::new(format!("UNDO (Z): {} LEFT", left)),
+			None => Text::new("UNDO (Z)"),
+		}
+	}
+
+	fn undo(self: &mut Game, ctx: &mut Context) {
+		if self.level_transition.is_some() {
+			return;
+		}
+
+		if let Some(previous) = self.history.undo(&self.rules) {
+			if let Some(recorder) = &mut self.recorder {
+				recorder.undo();
+			}
+
+			self.rules = previous;
+			LevelLoader::rebuild_cards(ctx, &self.rules, &mut self.cards);
+		}
+	}
+
+	// only in levels with energy, the others end the turn after every action
+	fn end_turn(self: &mut Game, ctx: &mut Context) {
+		if self.level_transition.is_none() && self.rules.can_end_turn() {
+			self.perform(ctx, Action::EndTurn);
+		}
+	}
+
+	fn redo(self: &mut Game, ctx: &mut Context) {
+		if self.level_transition.is_some() {
+			return;
+		}
+
+		if let Some(next) = self.history.redo(&self.rules) {
+			if let Some(recorder) = &mut self.recorder {
+				recorder.redo();
+			}
+
+			self.rules = next;
+			LevelLoader::rebuild_cards(ctx, &self.rules, &mut self.cards);
+		}
+	}
+
+	// tells the player that the level is done while the chest opens
+	fn draw_progress(self: &mut Game, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+		if self.level_transition.is_none() {
+			return Ok(());
+		}
+
+		let (screen_w, screen_h) = ctx.gfx.drawable_size();
+
+		let mut text = Text::new("LEVEL COMPLETE");
+		text.set_scale(16.0 * GlobConst::SCALE)
+			.set_layout(TextLayout::center());
+
+		canvas.draw(&text, DrawParam::default().dest(vec2(screen_w * 0.5, screen_h * 0.5)).z(2000));
+
+		Ok(())
+	}
+
+	// title and par of the current level, the hint only when asked for
+	fn draw_level_info(self: &mut Game, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+		let Some(campaign) = &self.campaign else {
+			return Ok(());
+		};
+
+		let level = campaign.current_level();
+		let mut info = format!("{} {}/{}: {}", campaign.pack.name.to_uppercase(), campaign.current_index() + 1, campaign.len(), level.title.to_uppercase());
+
+		if let Some(par) = level.par {
+			info += &format!("\nPAR: {} CARDS", par);
+		}
+
+		match (&level.hint, self.show_hint) {
+			(Some(hint), true) => info += &format!("\nHINT: {}", hint),
+			(Some(_), false) => info += "\nHINT (H)",
+			_ => (),
+		}
+
+		let (screen_w, screen_h) = ctx.gfx.drawable_size();
+
+		let mut text = Text::new(info);
+		text.set_layout(TextLayout::top_left());
+
+		canvas.draw(&text, DrawParam::default().dest(vec2(screen_w * 0.5 - 200.0, RESTART_BUTTON_POS.y)).z(2000));
+
+		Ok(())
+	}
+
+	fn use_card_at(self: &mut Game, ctx: &mut Context, idx: usize) -> GameResult {
+		self.perform(ctx, Action::PlayCard(idx));
+
+		Ok(())
+	}
+
+	// every change to the rules goes through here, so it can be undone
+	fn perform(self: &mut Game, ctx: &mut Context, action: Action) -> Option<Outcome> {
+		let before = self.rules.clone();
+		let outcome = self.rules.perform(action)?;
+
+		if let Outcome::Attacked(_) = outcome {
+			self.breakable_drawer.hit(&before, &self.rules);
+		}
+
+		self.history.record(before);
+
+		if let Some(recorder) = &mut self.recorder {
+			recorder.record(action);
+		}
+
+		// the card views mirror the hand in rules
+		if let Action::PlayCard(idx) = action {
+			self.cards.remove(idx);
+		}
+
+		// cards drawn after the play or at the end of the turn get a view too
+		for card_type in &self.rules.hand[self.cards.len()..] {
+			let mut card = Card::new(ctx, *card_type);
+			card.cost = self.rules.shown_cost(*card_type);
+			self.cards.push(card);
+		}
+
+		Some(outcome)
+	}
+}
+
+fn button_rect(ctx: &mut Context, text: &Text, pos: Vec2) -> GameResult<Rect> {
+	let size = text.measure(ctx)?;
+
+	Ok(Rect::new(pos.x, pos.y, size.x, size.y))
 }
 
 
@@ -195,11 +1000,35 @@ fn main() {
 		.build()
 		.expect("Couldn't create ggez context.");
 
-	let mut game = Game::new(&mut ctx);
+	// --seed <number> deals every shuffled level in the same order
+	let args: Vec<String> = std::env::args().collect();
+	let seed_override = args.iter()
+		.position(|arg| arg == "--seed")
+		.and_then(|i| args.get(i + 1))
+		.map(|seed| seed.parse::<u64>().expect("--seed needs a number"));
 
-	let lvl = LevelManager::load_level(&mut ctx, "resources/levels/level1.json", &mut game.cards, &mut game.player, &mut game.explainer).unwrap();
+	// --save <path> uses another save game than the one in the user data directory
+	let save_path = args.iter()
+		.position(|arg| arg == "--save")
+		.and_then(|i| args.get(i + 1))
+		.map(PathBuf::from)
+		.unwrap_or_else(|| ctx.fs.user_data_dir().join(SAVE_FILE));
 
-	// println!("{:#?}", lvl);
+	// --snapshot <path> starts right in the saved situation, F5 and F9 use the same file
+	let snapshot_arg = args.iter()
+		.position(|arg| arg == "--snapshot")
+		.and_then(|i| args.get(i + 1))
+		.map(PathBuf::from);
+	let snapshot_path = snapshot_arg.clone().unwrap_or_else(|| save_path.with_file_name(snapshot::SNAPSHOT_FILE));
+
+	// every pack in resources/levels, picked in the level select
+	let level_select = LevelSelect::new(Pack::discover_packs(PACKS_DIR));
+
+	let mut game = Game::new(&mut ctx, level_select, seed_override, save_path, snapshot_path);
+
+	if let Some(path) = snapshot_arg {
+		game.restore_snapshot(&mut ctx, &path).expect("couldn't restore the snapshot");
+	}
 
 	// let mut binding = Image::from_path(&mut ctx, "/glove_big.png").unwrap().to_pixels(&ctx).unwrap();
 	// let glove = Surface::from_data(&mut binding, 16, 16, 0, PixelFormatEnum::RGBA32).unwrap();
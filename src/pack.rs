@@ -0,0 +1,74 @@
+// level packs: a directory with a pack.json manifest and the level files it lists
+
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::level_manager::{read_manifest, PackLevel};
+
+
+pub const PACKS_DIR: &str = "resources/levels";
+pub const PACK_MANIFEST: &str = "pack.json";
+
+
+#[derive(Clone, Debug)]
+pub struct Pack {
+	pub dir: PathBuf,
+	pub name: String,
+	pub author: String,
+	pub levels: Vec<PackLevel>,
+}
+
+impl Pack {
+	pub fn load<P: AsRef<Path>>(dir: P) -> Result<Pack, Box<dyn Error>> {
+		let dir = dir.as_ref();
+		let manifest = read_manifest(dir.join(PACK_MANIFEST))?;
+
+		if manifest.levels.is_empty() {
+			return Err(format!("pack {} doesn't list any levels", dir.display()).into());
+		}
+
+		// a pack without a name is called like its directory
+		let name = if manifest.name.is_empty() {
+			dir.file_name().map_or(String::new(), |name| name.to_string_lossy().to_string())
+		} else {
+			manifest.name
+		};
+
+		Ok(Pack {
+			dir: dir.to_path_buf(),
+			name,
+			author: manifest.author,
+			levels: manifest.levels,
+		})
+	}
+
+	pub fn level_path(self: &Pack, idx: usize) -> PathBuf {
+		self.dir.join(&self.levels[idx].file)
+	}
+}
+
+// every directory in root with a manifest, sorted by directory name
+// broken packs are reported and left out, so one bad pack doesn't hide the others
+pub fn discover_packs<P: AsRef<Path>>(root: P) -> Vec<Pack> {
+	let Ok(entries) = fs::read_dir(&root) else {
+		eprintln!("no level packs found in {}", root.as_ref().display());
+		return vec!();
+	};
+
+	let mut dirs: Vec<PathBuf> = entries
+		.filter_map(|entry| entry.ok().map(|entry| entry.path()))
+		.filter(|path| path.join(PACK_MANIFEST).is_file())
+		.collect();
+	dirs.sort();
+
+	dirs.into_iter()
+		.filter_map(|dir| match Pack::load(&dir) {
+			Ok(pack) => Some(pack),
+			Err(e) => {
+				eprintln!("skipping level pack {}: {}", dir.display(), e);
+				None
+			},
+		})
+		.collect()
+}
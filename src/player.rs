@@ -1,8 +1,7 @@
 use ggez::{
 	graphics::*,
-	event::{self, EventHandler},
 	glam::*,
-	input::keyboard::{KeyCode, KeyMods, KeyInput},
+	input::keyboard::{KeyCode, KeyInput},
 	input::mouse::MouseButton,
 	Context, GameResult
 };
@@ -10,13 +9,16 @@ use ggez::{
 use crate::grid_drawer as GridDrawer;
 use crate::global_constants as GlobConst;
 use crate::math::sin;
+use crate::rules::{Rules, Grid, PendingAction, MAX_VALUE};
+
+pub use crate::rules::ValueType;
 
 use std::f32::consts::PI as PI;
 
 
-const NEIGHBORS: [(i32, i32); 12] = [(0, -2), (-1, -1), (0, -1), (1, -1), (-2, 0), (-1, 0), (1, 0), (2, 0), (-1, 1), (0, 1), (1, 1), (0, 2)];
-const KEY_NEIGHBORS: [(i32, i32); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
 const TRAVEL_SPEED: f32 = 10.0;
+const DANGER_COLOR: Color = Color::new(0.9, 0.15, 0.1, 0.45);
+const AIM_COLOR: Color = Color::new(1.0, 0.8, 0.3, 0.45);
 
 const COLORS: [[u8; 3]; 3] = [
 	[75, 202, 50],
@@ -27,7 +29,7 @@ const BAR_PXS: [f32; 5] = [100.0 / 255.0, 220.0 / 255.0, 161.0 / 255.0, 161.0 /
 
 const BAR_HEIGHT: f32 = 60.0;
 const BAR_PADDING: f32 = 15.0;
-const BAR_SEGMENTS: f32 = 3.0;
+const BAR_SEGMENTS: f32 = MAX_VALUE;		// one segment per point
 const BAR_SEGMENT_HEIGHT: f32 = (BAR_HEIGHT - 2.0) / BAR_SEGMENTS;
 
 
@@ -36,6 +38,7 @@ pub struct WalkRune {
 	sprite: Image,
 	pos: Vec2,
 	grid_pos: Vec2,
+	scale: f32,			// the scale of the tile field it floats over
 	timer: f32,
 
 	offset: f32,
@@ -56,6 +59,7 @@ impl WalkRune {
 			sprite: Image::from_path(ctx, "/walk_rune.png").unwrap(),
 			pos: Vec2::ZERO,
 			grid_pos: Vec2::ZERO,
+			scale: GlobConst::SCALE,
 			timer: 0.0,
 
 			offset: -8.0,
@@ -69,14 +73,15 @@ impl WalkRune {
 		}
 	}
 
-	fn update(self: &mut WalkRune, ctx: &mut Context, dt: &f32) -> GameResult {
+	fn update(self: &mut WalkRune, ctx: &mut Context, dt: &f32, grid: &Grid) -> GameResult {
 		self.timer += dt;
+		self.scale = GridDrawer::field_scale(ctx, grid);
 
-		if let Some(rune_pos) = GridDrawer::mouse_pos_on_grid(ctx)? {
+		if let Some(rune_pos) = GridDrawer::mouse_pos_on_grid(ctx, grid)? {
 			self.drawing = true;
 
 			let mut screen_rp = Vec2::ZERO;
-			GridDrawer::grid_pos_to_screen(ctx, &rune_pos, &mut screen_rp)?;
+			GridDrawer::grid_pos_to_screen(ctx, grid, &rune_pos, &mut screen_rp)?;
 			self.grid_pos = rune_pos;
 
 			self.pos = screen_rp;
@@ -88,7 +93,7 @@ impl WalkRune {
 		Ok(())
 	}
 
-	fn draw(self: &mut WalkRune, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+	fn draw(self: &mut WalkRune, _ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
 		if !self.drawing {
 			return Ok(());
 		}
@@ -96,9 +101,9 @@ impl WalkRune {
 		let dp = DrawParam::default()
 			.dest_rect(Rect::new(
 				self.pos.x,
-				self.pos.y + self.float * GlobConst::SCALE,
-				GridDrawer::TILE_SIZE / self.sprite.width() as f32 * GlobConst::SCALE,
-				GridDrawer::TILE_SIZE / self.sprite.height() as f32 * GlobConst::SCALE
+				self.pos.y + self.float * self.scale,
+				GridDrawer::TILE_SIZE / self.sprite.width() as f32 * self.scale,
+				GridDrawer::TILE_SIZE / self.sprite.height() as f32 * self.scale
 			))
 			.offset(vec2(0.5, 0.5))
 			.rotation(self.rot * PI / 180.0)
@@ -109,36 +114,25 @@ impl WalkRune {
 		Ok(())
 	}
 
-	fn on_possible_position(self: &mut WalkRune, possible_positions: &Vec<(i32, i32)>) -> bool {
+	fn on_possible_position(self: &mut WalkRune, possible_positions: &[(i32, i32)]) -> bool {
 		 possible_positions.contains(&(self.grid_pos.x as i32, self.grid_pos.y as i32))
 	}
 }
 
 
-pub enum ValueType {
-	Attack = 0,
-	Armor = 1,
-	Health = 2,
-}
-
+// only draws the player, the position and values live in Rules
 pub struct Player {
-	pub pos: Vec2,		// position in the grid
-	pub target_pos: Vec2,
+	pub pos: Vec2,		// animated position in the grid
 	screen_pos: Vec2,	// position on screen
 	sprite: Image,
-	rune_positions: Vec<(i32, i32)>,
 	rune_sprite: Image,
 
 	walk_rune: WalkRune,
-	moving: bool,
-	using_key: bool,
 
 	bars: [Image; 3],
 	icons: Image,
 	frame: Image,
 	separator: Image,
-
-	values: [f32; 3],
 }
 
 impl Player {
@@ -166,103 +160,95 @@ impl Player {
 
 		Player {
 			pos: vec2(x as f32, y as f32),
-			target_pos: Vec2::ZERO,
 			screen_pos: vec2(0.0, 0.0),
 			sprite: Image::from_path(ctx, "/player.png").unwrap(),
-			rune_positions: vec!(),
 			rune_sprite: Image::from_path(ctx, "/rune.png").unwrap(),
 
 			walk_rune: WalkRune::new(ctx),
-			moving: false,
-			using_key: false,
 
 			bars: [red_bar, blue_bar, green_bar],
-			icons: icons,
+			icons,
 			frame: Image::from_path(ctx, "/frame.png").unwrap(),
 			separator: Image::from_path(ctx, "/separator.png").unwrap(),
-			
-			values: [3.0, 2.0, 1.0],
 		}
 	}
 
-	pub fn update(self: &mut Player, ctx: &mut Context, dt: &f32) -> GameResult {
+	// jump to the position in rules without animating, e.g. after loading a level
+	pub fn snap_to(self: &mut Player, rules: &Rules) {
+		self.pos = vec2(rules.player_pos.0 as f32, rules.player_pos.1 as f32);
+	}
+
+	// returns the rune position that was clicked, the game decides what happens with it
+	pub fn update(self: &mut Player, ctx: &mut Context, dt: &f32, rules: &Rules) -> GameResult<Option<(i32, i32)>> {
+		let target_pos = vec2(rules.player_pos.0 as f32, rules.player_pos.1 as f32);
+
 		// convert grid position to screen position
-		GridDrawer::grid_pos_to_screen(ctx, &self.pos, &mut self.screen_pos)?;
+		GridDrawer::grid_pos_to_screen(ctx, &rules.grid, &self.pos, &mut self.screen_pos)?;
 		
-		self.pos = self.pos.lerp(self.target_pos, TRAVEL_SPEED * *dt);
+		self.pos = self.pos.lerp(target_pos, TRAVEL_SPEED * *dt);
 		
-		if self.pos.distance(self.target_pos) <= 0.05 {
-			self.pos = self.target_pos;
+		if self.pos.distance(target_pos) <= 0.05 {
+			self.pos = target_pos;
 		}
 
 		// pivot at the feet
-		self.screen_pos -= vec2(self.sprite.width() as f32 * 0.5, self.sprite.height() as f32 * 0.75) * GlobConst::SCALE;
+		self.screen_pos -= vec2(self.sprite.width() as f32 * 0.5, self.sprite.height() as f32 * 0.75) * GridDrawer::field_scale(ctx, &rules.grid);
 
-		if self.moving || self.using_key {
-			self.walk_rune.update(ctx, dt)?;
+		if rules.pending.is_some() {
+			self.walk_rune.update(ctx, dt, &rules.grid)?;
 			
-			if ctx.mouse.button_just_pressed(MouseButton::Left) && self.walk_rune.on_possible_position(&self.rune_positions) {
-				if self.moving {
-					self.target_pos = self.walk_rune.grid_pos;
-					self.moving = false;
-					self.rune_positions.clear();
-				}
+			if ctx.mouse.button_just_pressed(MouseButton::Left) && self.walk_rune.on_possible_position(&rules.targets) {
+				let target = (self.walk_rune.grid_pos.x as i32, self.walk_rune.grid_pos.y as i32);
 
-				else if self.using_key {
-					println!("OPEN_CHEST");
-					self.using_key = false;
-					self.rune_positions.clear();
-				}
+				return Ok(Some(target));
 			}
 		}
 
-		Ok(())
+		Ok(None)
 	}
 
-	pub fn draw(self: &mut Player, ctx: &mut Context, canvas: &mut Canvas, quad_mesh: &Mesh) -> GameResult {
+	pub fn draw(self: &mut Player, ctx: &mut Context, canvas: &mut Canvas, quad_mesh: &Mesh, rules: &Rules) -> GameResult {
 
 		// draw rune positions, e.g. positions where the player can stop on
-		for rp in &self.rune_positions {
-			let mut screen_pos = Vec2::ZERO;
-			GridDrawer::grid_pos_to_screen(ctx, &vec2(rp.0 as f32, rp.1 as f32), &mut screen_pos)?;
-			let dp = DrawParam::default()
-				.dest_rect(Rect::new(
-					screen_pos.x - GridDrawer::TILE_SIZE as f32 * 0.5 * GlobConst::SCALE,
-					screen_pos.y - GridDrawer::TILE_SIZE as f32 * 0.5 * GlobConst::SCALE,
-					GridDrawer::TILE_SIZE as f32 / self.rune_sprite.width() as f32 * GlobConst::SCALE,
-					GridDrawer::TILE_SIZE as f32 / self.rune_sprite.height() as f32 * GlobConst::SCALE
-				))
-				.z(1000);
-
-			canvas.draw(&self.rune_sprite, dp);
+		draw_fields(ctx, canvas, &rules.grid, &rules.targets, &self.rune_sprite, self.rune_sprite.width() as f32, DrawParam::default().z(1000))?;
+
+		// while picking where to move, the fields the enemies attack next are tinted below the runes
+		if rules.pending == Some(PendingAction::Move) {
+			draw_fields(ctx, canvas, &rules.grid, &rules.danger_zone(), quad_mesh, GlobConst::QUAD_SIZE, DrawParam::default().color(DANGER_COLOR).z(999))?;
+		}
+
+		// while aiming an attack, the fields it would hit are tinted below the runes
+		if let Some(PendingAction::Attack(attack)) = rules.pending.filter(|_| self.walk_rune.drawing && self.walk_rune.on_possible_position(&rules.targets)) {
+			let aim = (self.walk_rune.grid_pos.x as i32, self.walk_rune.grid_pos.y as i32);
+			draw_fields(ctx, canvas, &rules.grid, &rules.attack_fields(attack, aim), quad_mesh, GlobConst::QUAD_SIZE, DrawParam::default().color(AIM_COLOR).z(999))?;
 		}
 
 		// draw rune
-		if self.moving || self.using_key {
+		if rules.pending.is_some() {
 			self.walk_rune.draw(ctx, canvas)?;
 		}
 
 		// maybe save player rect in the struct?
 		let draw_param = DrawParam::default()
 			.dest(vec2(self.screen_pos.x, self.screen_pos.y))
-			.scale(GlobConst::SCALE_VECTOR)
+			.scale(Vec2::ONE * GridDrawer::field_scale(ctx, &rules.grid))
 			.z(self.screen_pos.y as i32);
 
 		canvas.draw(&self.sprite, draw_param);
 		
-		self.draw_icons(ctx, canvas)?;
+		self.draw_icons(ctx, canvas, &rules.values)?;
 
 		Ok(())
 	}
 
-	fn draw_icons(self: &mut Player, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+	fn draw_icons(self: &mut Player, ctx: &mut Context, canvas: &mut Canvas, values: &[f32; 3]) -> GameResult {
 		let mut dp = DrawParam::default()
 			.scale(GlobConst::SCALE_VECTOR);
 
-		let (width, height) = ctx.gfx.drawable_size();
-		let (width_half, height_half) = (width as f32 * 0.5, height as f32 * 0.5);
+		let (_, height) = ctx.gfx.drawable_size();
+		let height_half = height * 0.5;
 
-		for i in 0..3 {
+		for (i, value) in values.iter().enumerate() {
 			
 			let height = height_half - (i as f32 - 1.0) * (BAR_HEIGHT + BAR_PADDING) * GlobConst::SCALE + BAR_HEIGHT * 0.5 * GlobConst::SCALE;
 			let x = 5.0;
@@ -276,11 +262,11 @@ impl Player {
 
 			for j in 0..BAR_SEGMENTS as u32 {
 
-				if j as f32 <= self.values[i] - 1.0 {
+				if j as f32 <= value - 1.0 {
 					dp = dp.dest(bar_pos + Vec2::ONE * GlobConst::SCALE + vec2(0.0, (BAR_SEGMENTS - j as f32 - 1.0) * BAR_SEGMENT_HEIGHT * GlobConst::SCALE))
 						.scale(vec2(GlobConst::SCALE, (BAR_SEGMENT_HEIGHT - 1.0) * GlobConst::SCALE));
 
-					canvas.draw(&self.bars[i as usize], dp);
+					canvas.draw(&self.bars[i], dp);
 
 				}
 
@@ -303,16 +289,7 @@ impl Player {
 		Ok(())
 	}
 
-	
-	pub fn change_at(self: &mut Player, v_type: ValueType, amount: f32) -> GameResult {
-		
-		self.values[v_type as usize] += amount;
-
-		Ok(())
-	}
-
-
-	pub fn key_down(self: &mut Player, ctx: &mut Context, input: KeyInput, _repeat: bool) -> GameResult {
+	pub fn key_down(self: &mut Player, _ctx: &mut Context, input: KeyInput, _repeat: bool, grid: &Grid) -> GameResult {
 		
 		// move the players position in the grid
 		match input.keycode {
@@ -324,49 +301,28 @@ impl Player {
 		}
 
 		// keep player in grid
-		self.pos = self.pos.clamp(Vec2::ZERO, Vec2::ONE * (GridDrawer::TILES_PER_ROW - 1.0));
+		self.pos = self.pos.clamp(Vec2::ZERO, vec2(grid.width() as f32 - 1.0, grid.height() as f32 - 1.0));
 
 		Ok(())
 	}
+}
 
-	pub fn move_player(self: &mut Player) {
-		for neighbor in NEIGHBORS {
-			let (x, y) = (neighbor.0 + self.pos.x as i32, neighbor.1 + self.pos.y as i32);
-			if x < 0 || x >= GridDrawer::TILES_PER_ROW as i32 || y < 0 || y >= GridDrawer::TILES_PER_ROW as i32 {
-				continue;
-			}
-
-			if neighbor.0 * neighbor.0 == 4 || neighbor.1 * neighbor.1 == 4 {
-				println!("{}, {}", neighbor.0, neighbor.1);
-				let (mx, my) = (neighbor.0 / 2 + self.pos.x as i32, neighbor.1 / 2 + self.pos.y as i32);
-				if GridDrawer::get_state(mx as usize, my as usize) != GridDrawer::State::Empty {
-					continue;
-				}
-			}
-
-			if GridDrawer::get_state(x as usize, y as usize) != GridDrawer::State::Empty {
-				continue;
-			}
+// covers every field with the drawable, size is its width in pixels, color and z come from base
+fn draw_fields(ctx: &mut Context, canvas: &mut Canvas, grid: &Grid, fields: &[(i32, i32)], drawable: &impl Drawable, size: f32, base: DrawParam) -> GameResult {
+	for (x, y) in fields {
+		let mut screen_pos = Vec2::ZERO;
+		GridDrawer::grid_pos_to_screen(ctx, grid, &vec2(*x as f32, *y as f32), &mut screen_pos)?;
+		let field_size = GridDrawer::field_size(ctx, grid);
+		let dp = base
+			.dest_rect(Rect::new(
+				screen_pos.x - field_size * 0.5,
+				screen_pos.y - field_size * 0.5,
+				field_size / size,
+				field_size / size
+			));
 
-			self.rune_positions.push((x, y));
-		}
-		self.moving = true;
+		canvas.draw(drawable, dp);
 	}
 
-	pub fn use_key(self: &mut Player) {
-		for neighbor in KEY_NEIGHBORS {
-			let (x, y) = (neighbor.0 + self.pos.x as i32, neighbor.1 + self.pos.y as i32);
-
-			if x < 0 || x >= GridDrawer::TILES_PER_ROW as i32 || y < 0 || y >= GridDrawer::TILES_PER_ROW as i32 {
-				continue;
-			}
-
-			if GridDrawer::get_state(x as usize, y as usize) != GridDrawer::State::Chest {
-				continue;
-			}
-
-			self.rune_positions.push((x, y));
-		}
-		self.using_key = true;
-	}
+	Ok(())
 }
@@ -0,0 +1,320 @@
+// a recording of every action in a level, it is played back in the game or checked without a window
+// replays are stored next to their level, e.g. level1.json.replay, and kept as a corpus of known solutions
+
+use serde::{Serialize, Deserialize};
+
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::level_manager::Level;
+use crate::rules::{Rules, Action};
+
+
+pub const REPLAY_VERSION: u32 = 1;
+pub const REPLAY_EXTENSION: &str = "replay";
+
+const MIN_SPEED: f32 = 0.25;
+const MAX_SPEED: f32 = 8.0;
+
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy, Debug)]
+pub struct ReplayStep {
+	pub time: f32,				// seconds since the level started
+	pub action: Action,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Replay {
+	pub version: u32,
+	pub seed: u64,				// shuffled levels need the same seed to deal the same cards
+	pub steps: Vec<ReplayStep>,
+}
+
+impl Replay {
+	pub fn new(seed: u64) -> Replay {
+		Replay {
+			version: REPLAY_VERSION,
+			seed,
+			steps: vec!(),
+		}
+	}
+}
+
+pub fn replay_path<P: AsRef<Path>>(level_path: P) -> PathBuf {
+	let mut name = level_path.as_ref().file_name().unwrap_or_default().to_os_string();
+	name.push(".");
+	name.push(REPLAY_EXTENSION);
+	level_path.as_ref().with_file_name(name)
+}
+
+pub fn replay_to_json(replay: &Replay) -> String {
+	serde_json::to_string_pretty(replay).unwrap()
+}
+
+pub fn parse_replay(text: &str) -> Result<Replay, Box<dyn Error>> {
+	let replay: Replay = serde_json::from_str(text)?;
+
+	if replay.version != REPLAY_VERSION {
+		return Err(format!("replay version {} isn't supported", replay.version).into());
+	}
+
+	Ok(replay)
+}
+
+pub fn read_replay<P: AsRef<Path>>(path: P) -> Result<Replay, Box<dyn Error>> {
+	parse_replay(&fs::read_to_string(path)?)
+}
+
+pub fn write_replay<P: AsRef<Path>>(path: P, replay: &Replay) -> Result<(), Box<dyn Error>> {
+	let mut file = File::create(path)?;
+	file.write_all(replay_to_json(replay).as_bytes())?;
+
+	Ok(())
+}
+
+// the seed a replay of the level has to be recorded with, None if every seed deals the same cards
+// or the level picks a random one, seed_override is a seed given on the command line
+pub fn required_seed(level: &Level, seed_override: Option<u64>) -> Option<u64> {
+	if !level.shuffle {
+		return None;
+	}
+
+	seed_override.or(level.seed)
+}
+
+// plays every step on a fresh start of the level, fails on the first step that isn't possible
+pub fn run(level: &Level, replay: &Replay) -> Result<Rules, Box<dyn Error>> {
+	if let Some(seed) = required_seed(level, None).filter(|seed| *seed != replay.seed) {
+		return Err(format!("the replay was recorded with seed {}, but the level deals with seed {}", replay.seed, seed).into());
+	}
+
+	let mut rules = level.rules(replay.seed);
+
+	for (i, step) in replay.steps.iter().enumerate() {
+		if rules.perform(step.action).is_none() {
+			return Err(format!("step {}: {:?} isn't possible", i + 1, step.action).into());
+		}
+	}
+
+	Ok(rules)
+}
+
+
+// records while a level is played, steps taken back with undo come back with redo
+pub struct Recorder {
+	replay: Replay,
+	undone: Vec<ReplayStep>,
+	time: f32,
+}
+
+impl Recorder {
+	pub fn new(seed: u64) -> Recorder {
+		Recorder {
+			replay: Replay::new(seed),
+			undone: vec!(),
+			time: 0.0,
+		}
+	}
+
+	pub fn replay(self: &Recorder) -> &Replay {
+		&self.replay
+	}
+
+	pub fn tick(self: &mut Recorder, dt: f32) {
+		self.time += dt;
+	}
+
+	pub fn record(self: &mut Recorder, action: Action) {
+		self.replay.steps.push(ReplayStep { time: self.time, action });
+		self.undone.clear();
+	}
+
+	pub fn undo(self: &mut Recorder) {
+		if let Some(step) = self.replay.steps.pop() {
+			self.undone.push(step);
+		}
+	}
+
+	pub fn redo(self: &mut Recorder) {
+		if let Some(step) = self.undone.pop() {
+			self.replay.steps.push(step);
+		}
+	}
+}
+
+
+// hands out the steps of a replay at their time, scaled by the speed
+pub struct Playback {
+	replay: Replay,
+	next: usize,				// index of the next step
+	time: f32,
+	pub speed: f32,
+	pub paused: bool,
+}
+
+impl Playback {
+	pub fn new(replay: Replay) -> Playback {
+		Playback {
+			replay,
+			next: 0,
+			time: 0.0,
+			speed: 1.0,
+			paused: false,
+		}
+	}
+
+	// the level has to be started with it to deal the recorded cards
+	pub fn seed(self: &Playback) -> u64 {
+		self.replay.seed
+	}
+
+	// steps played so far and the number of steps
+	pub fn progress(self: &Playback) -> (usize, usize) {
+		(self.next, self.replay.steps.len())
+	}
+
+	pub fn is_finished(self: &Playback) -> bool {
+		self.next >= self.replay.steps.len()
+	}
+
+	pub fn faster(self: &mut Playback) {
+		self.speed = (self.speed * 2.0).min(MAX_SPEED);
+	}
+
+	pub fn slower(self: &mut Playback) {
+		self.speed = (self.speed * 0.5).max(MIN_SPEED);
+	}
+
+	// stops the playback for good, e.g. when a step doesn't fit the level
+	pub fn stop(self: &mut Playback) {
+		self.next = self.replay.steps.len();
+		self.paused = true;
+	}
+
+	// at most one step per frame, so every step can be seen
+	pub fn update(self: &mut Playback, dt: f32) -> Option<Action> {
+		if self.paused {
+			return None;
+		}
+
+		self.time += dt * self.speed;
+
+		let step = self.replay.steps.get(self.next)?;
+		if step.time > self.time {
+			return None;
+		}
+
+		self.next += 1;
+		Some(step.action)
+	}
+
+	// the next step right away, the playback pauses so it can be stepped through
+	pub fn step(self: &mut Playback) -> Option<Action> {
+		self.paused = true;
+
+		let step = self.replay.steps.get(self.next)?;
+		self.time = step.time;
+		self.next += 1;
+
+		Some(step.action)
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::level_manager::read_level;
+	use crate::pack::PACKS_DIR;
+
+	fn shuffled_level() -> Level {
+		read_level(Path::new(PACKS_DIR).join("basics/level3.json")).unwrap()
+	}
+
+	#[test]
+	fn bundled_replays_open_the_chest() {
+		for level in ["basics/level3.json", "ambush/level4.json", "training/level2.json"] {
+			let path = Path::new(PACKS_DIR).join(level);
+			let replay = read_replay(replay_path(&path)).unwrap();
+
+			assert!(run(&read_level(&path).unwrap(), &replay).unwrap().completed, "{}", level);
+		}
+	}
+
+	#[test]
+	fn replays_round_trip() {
+		let mut replay = Replay::new(12);
+		replay.steps.push(ReplayStep { time: 0.5, action: Action::PlayCard(1) });
+		replay.steps.push(ReplayStep { time: 1.0, action: Action::ChooseTarget((2, 3)) });
+		replay.steps.push(ReplayStep { time: 2.0, action: Action::EndTurn });
+
+		let parsed = parse_replay(&replay_to_json(&replay)).unwrap();
+		assert_eq!(parsed.seed, 12);
+		assert_eq!(parsed.steps, replay.steps);
+	}
+
+	#[test]
+	fn impossible_steps_fail() {
+		let mut level = shuffled_level();
+		level.seed = None;
+
+		let mut replay = Replay::new(0);
+		replay.steps.push(ReplayStep { time: 0.0, action: Action::ChooseTarget((0, 0)) });
+
+		let error = run(&level, &replay).unwrap_err();
+		assert!(error.to_string().starts_with("step 1"), "{}", error);
+	}
+
+	#[test]
+	fn fixed_seeds_have_to_match() {
+		let mut level = shuffled_level();
+		level.seed = Some(5);
+
+		assert_eq!(required_seed(&level, None), Some(5));
+		assert_eq!(required_seed(&level, Some(6)), Some(6));
+		assert!(run(&level, &Replay::new(6)).is_err());
+		assert!(run(&level, &Replay::new(5)).is_ok());
+
+		// without shuffling every seed deals the same
+		level.shuffle = false;
+		assert_eq!(required_seed(&level, None), None);
+	}
+
+	#[test]
+	fn the_recorder_takes_back_undone_steps() {
+		let mut recorder = Recorder::new(3);
+		recorder.record(Action::PlayCard(0));
+		recorder.tick(1.0);
+		recorder.record(Action::ChooseTarget((1, 1)));
+
+		recorder.undo();
+		assert_eq!(recorder.replay().steps.len(), 1);
+		recorder.redo();
+		assert_eq!(recorder.replay().steps[1], ReplayStep { time: 1.0, action: Action::ChooseTarget((1, 1)) });
+
+		// a new step after an undo drops the undone ones
+		recorder.undo();
+		recorder.record(Action::EndTurn);
+		recorder.redo();
+		assert_eq!(recorder.replay().steps.iter().map(|step| step.action).collect::<Vec<_>>(), vec!(Action::PlayCard(0), Action::EndTurn));
+	}
+
+	#[test]
+	fn playback_hands_out_steps_at_their_time() {
+		let mut replay = Replay::new(0);
+		replay.steps.push(ReplayStep { time: 1.0, action: Action::PlayCard(0) });
+		replay.steps.push(ReplayStep { time: 1.0, action: Action::ChooseTarget((1, 0)) });
+
+		let mut playback = Playback::new(replay);
+		assert_eq!(playback.update(0.5), None);
+
+		// one step per frame, even if both are due
+		playback.faster();
+		assert_eq!(playback.update(0.5), Some(Action::PlayCard(0)));
+		assert_eq!(playback.update(0.0), Some(Action::ChooseTarget((1, 0))));
+		assert!(playback.is_finished());
+		assert_eq!(playback.progress(), (2, 2));
+	}
+}
@@ -0,0 +1,958 @@
+// headless game rules: no Context, no images, just the state of a level
+// Game, Player and Card only draw what is stored in here
+
+use serde::{Serialize, Deserialize};
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+pub use crate::grid::{Grid, State};
+use crate::enemy::{Enemy, Intent};
+use crate::breakable::Breakable;
+
+
+const NEIGHBORS: [(i32, i32); 12] = [(0, -2), (-1, -1), (0, -1), (1, -1), (-2, 0), (-1, 0), (1, 0), (2, 0), (-1, 1), (0, 1), (1, 1), (0, 2)];
+const KEY_NEIGHBORS: [(i32, i32); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+
+pub const MAX_VALUE: f32 = 3.0;			// attack, armor and health never go above this
+
+
+// level files, save games and card costs use the names, e.g. "Move" or "Attack Line 3"
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug)]
+#[serde(try_from = "String", into = "String")]
+pub enum CardType {
+	Move,
+	Armor,
+	Health,
+	Key,
+	Attack(AttackCard),		// every Attack card has its own shape and range
+}
+
+impl CardType {
+	// energy it takes in levels with turns, unless the level sets its own cost
+	pub fn default_cost(self: CardType) -> u32 {
+		match self {
+			CardType::Move => 1,
+			CardType::Armor => 1,
+			CardType::Health => 2,
+			CardType::Key => 1,
+			CardType::Attack(_) => 1,
+		}
+	}
+
+	// position in the card sprite sheet and in the Explainer, the same for every Attack card
+	pub fn index(self: CardType) -> usize {
+		match self {
+			CardType::Move => 0,
+			CardType::Armor => 1,
+			CardType::Health => 2,
+			CardType::Key => 3,
+			CardType::Attack(_) => 4,
+		}
+	}
+
+	// case doesn't matter, "Attack" alone is the default melee attack
+	pub fn from_name(name: &str) -> Result<CardType, String> {
+		let name = name.trim();
+		let (kind, attack) = name.split_once(char::is_whitespace).unwrap_or((name, ""));
+
+		match (kind.to_lowercase().as_str(), attack.trim()) {
+			("move", "") => Ok(CardType::Move),
+			("armor", "") => Ok(CardType::Armor),
+			("health", "") => Ok(CardType::Health),
+			("key", "") => Ok(CardType::Key),
+			("attack", "") => Ok(CardType::Attack(AttackCard::MELEE)),
+			("attack", attack) => Ok(CardType::Attack(AttackCard::from_text(attack)?)),
+			_ => Err(format!("unknown card \"{}\"", name)),
+		}
+	}
+}
+
+impl fmt::Display for CardType {
+	fn fmt(self: &CardType, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			CardType::Move => write!(f, "Move"),
+			CardType::Armor => write!(f, "Armor"),
+			CardType::Health => write!(f, "Health"),
+			CardType::Key => write!(f, "Key"),
+			CardType::Attack(attack) if *attack == AttackCard::MELEE => write!(f, "Attack"),
+			CardType::Attack(attack) => write!(f, "Attack {}", attack.to_text()),
+		}
+	}
+}
+
+impl TryFrom<String> for CardType {
+	type Error = String;
+
+	fn try_from(name: String) -> Result<CardType, String> {
+		CardType::from_name(&name)
+	}
+}
+
+impl From<CardType> for String {
+	fn from(card: CardType) -> String {
+		card.to_string()
+	}
+}
+
+// which fields an Attack card can aim at and which it hits
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug)]
+pub enum AttackShape {
+	Melee,			// one field within range
+	Line,			// a direction, hits everything in it up to the range, trees and chests stop it
+	Area,			// a field within range and the four around it
+}
+
+// the shape and range of one Attack card, one field next to the player by default
+#[derive(Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug)]
+pub struct AttackCard {
+	pub shape: AttackShape,
+	pub range: u32,
+}
+
+impl AttackCard {
+	// a plain "Attack" card
+	pub const MELEE: AttackCard = AttackCard { shape: AttackShape::Melee, range: 1 };
+
+	// the "Line 3" in "Attack Line 3", the range can be left out
+	pub fn from_text(text: &str) -> Result<AttackCard, String> {
+		let mut parts = text.split_whitespace();
+
+		let name = parts.next().ok_or("the attack card needs a shape")?;
+		let shape = match name.to_lowercase().as_str() {
+			"melee" => AttackShape::Melee,
+			"line" => AttackShape::Line,
+			"area" => AttackShape::Area,
+			_ => return Err(format!("unknown attack shape \"{}\"", name)),
+		};
+
+		let range = match parts.next() {
+			Some(range) => range.parse().map_err(|_| format!("the attack range can't be \"{}\"", range))?,
+			None => AttackCard::MELEE.range,
+		};
+
+		if let Some(extra) = parts.next() {
+			return Err(format!("unexpected \"{}\" after the attack range", extra));
+		}
+
+		Ok(AttackCard { shape, range })
+	}
+
+	pub fn to_text(self: AttackCard) -> String {
+		format!("{:?} {}", self.shape, self.range)
+	}
+}
+
+// the cost a level sets for the card or the default one
+// a cost for "Attack" also counts for the Attack cards with their own shape, unless they have a cost of their own
+pub fn card_cost(costs: &BTreeMap<CardType, u32>, card: CardType) -> u32 {
+	let fallback = match card {
+		CardType::Attack(_) => costs.get(&CardType::Attack(AttackCard::MELEE)),
+		_ => None,
+	};
+
+	costs.get(&card).or(fallback).copied().unwrap_or_else(|| card.default_cost())
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum ValueType {
+	Attack = 0,
+	Armor = 1,
+	Health = 2,
+}
+
+// what a hit did, armor takes the damage before health
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Hit {
+	pub absorbed: f32,				// taken by armor
+	pub taken: f32,					// taken by health
+	pub defeated: bool,				// health reached zero
+}
+
+// a card that waits for the player to pick one of the rune positions
+#[derive(Serialize, Deserialize, PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum PendingAction {
+	Move,
+	Key,
+	Attack(AttackCard),
+}
+
+impl PendingAction {
+	// the card that was played to start it
+	pub fn card(self: PendingAction) -> CardType {
+		match self {
+			PendingAction::Move => CardType::Move,
+			PendingAction::Key => CardType::Key,
+			PendingAction::Attack(attack) => CardType::Attack(attack),
+		}
+	}
+}
+
+// what happened after an action
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Outcome {
+	CardPlayed(CardType),
+	Moved((i32, i32)),
+	ChestOpened((i32, i32)),
+	Attacked((i32, i32)),
+	TurnEnded,
+}
+
+// everything the player can do, undo works on these
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Action {
+	PlayCard(usize),				// index into the hand
+	ChooseTarget((i32, i32)),		// one of the targets of the pending card
+	EndTurn,						// only in levels with energy, the others end the turn after every action
+}
+
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
+pub struct Rules {
+	pub grid: Grid,
+	pub player_pos: (i32, i32),
+	pub values: [f32; 3],
+	pub hand: Vec<CardType>,
+	pub hand_size: usize,				// the hand is filled up to this after every play
+	pub draw_pile: Vec<CardType>,		// the next card to draw is the last one
+	pub discard_pile: Vec<CardType>,
+	pub pending: Option<PendingAction>,
+	pub targets: Vec<(i32, i32)>,		// positions the pending action can be used on
+	pub completed: bool,				// a chest was opened
+	pub seed: u64,					// the deck was shuffled with it
+	#[serde(default)]
+	pub enemies: Vec<Enemy>,			// they act at the end of every turn
+	#[serde(default = "first_turn")]
+	pub turn: u32,
+	#[serde(default)]
+	pub energy: u32,					// left in this turn
+	#[serde(default)]
+	pub energy_per_turn: Option<u32>,	// None: no energy, every finished action is a turn of its own
+	#[serde(default)]
+	pub card_costs: BTreeMap<CardType, u32>,		// the costs the level changes, the others are the defaults
+	#[serde(default)]
+	pub turn_limit: Option<u32>,		// the chest has to be opened within this many turns
+	#[serde(default)]
+	pub breakables: Vec<Breakable>,		// broken ones are removed
+}
+
+fn first_turn() -> u32 {
+	1
+}
+
+impl Default for Rules {
+	fn default() -> Self {
+		Rules::new()
+	}
+}
+
+impl Rules {
+	pub fn new() -> Rules {
+		Rules {
+			grid: Grid::default(),
+			player_pos: (0, 0),
+			values: [3.0, 2.0, 1.0],
+			hand: vec!(),
+			hand_size: 0,
+			draw_pile: vec!(),
+			discard_pile: vec!(),
+			pending: None,
+			targets: vec!(),
+			completed: false,
+			seed: 0,
+			enemies: vec!(),
+			turn: first_turn(),
+			energy: 0,
+			energy_per_turn: None,
+			card_costs: BTreeMap::new(),
+			turn_limit: None,
+			breakables: vec!(),
+		}
+	}
+
+	pub fn uses_energy(self: &Rules) -> bool {
+		self.energy_per_turn.is_some()
+	}
+
+	pub fn card_cost(self: &Rules, card: CardType) -> u32 {
+		card_cost(&self.card_costs, card)
+	}
+
+	// the cost to show on a card, levels without energy don't show any
+	pub fn shown_cost(self: &Rules, card: CardType) -> Option<u32> {
+		self.uses_energy().then(|| self.card_cost(card))
+	}
+
+	fn can_afford(self: &Rules, card: CardType) -> bool {
+		!self.uses_energy() || self.card_cost(card) <= self.energy
+	}
+
+	// the turn limit has passed without the chest being opened
+	pub fn is_out_of_turns(self: &Rules) -> bool {
+		!self.completed && self.turn_limit.is_some_and(|limit| self.turn > limit)
+	}
+
+	// nothing can be done anymore
+	fn is_over(self: &Rules) -> bool {
+		self.is_defeated() || self.is_out_of_turns()
+	}
+
+	pub fn value(self: &Rules, v_type: ValueType) -> f32 {
+		self.values[v_type as usize]
+	}
+
+	// values stay between 0 and MAX_VALUE, use damage, heal and add_armor from outside
+	fn change_at(self: &mut Rules, v_type: ValueType, amount: f32) {
+		let value = &mut self.values[v_type as usize];
+		*value = (*value + amount).clamp(0.0, MAX_VALUE);
+	}
+
+	// every source of damage goes through here
+	pub fn damage(self: &mut Rules, amount: f32) -> Hit {
+		let amount = amount.max(0.0);
+
+		let absorbed = amount.min(self.value(ValueType::Armor));
+		self.change_at(ValueType::Armor, -absorbed);
+
+		let taken = (amount - absorbed).min(self.value(ValueType::Health));
+		self.change_at(ValueType::Health, -taken);
+
+		Hit { absorbed, taken, defeated: self.is_defeated() }
+	}
+
+	pub fn heal(self: &mut Rules, amount: f32) {
+		self.change_at(ValueType::Health, amount.max(0.0));
+	}
+
+	pub fn add_armor(self: &mut Rules, amount: f32) {
+		self.change_at(ValueType::Armor, amount.max(0.0));
+	}
+
+	// the damage the player deals
+	pub fn attack_damage(self: &Rules) -> f32 {
+		self.value(ValueType::Attack)
+	}
+
+	pub fn is_defeated(self: &Rules) -> bool {
+		self.value(ValueType::Health) <= 0.0
+	}
+
+	// draws from the draw pile until the hand is full or the pile is empty
+	pub fn draw_cards(self: &mut Rules) {
+		while self.hand.len() < self.hand_size {
+			let Some(card) = self.draw_pile.pop() else {
+				break;
+			};
+
+			self.hand.push(card);
+		}
+	}
+
+	// plays the card at idx from the hand, returns false if it couldn't be played
+	pub fn play_card(self: &mut Rules, idx: usize) -> bool {
+		if idx >= self.hand.len() || self.pending.is_some() || self.is_over() || !self.can_afford(self.hand[idx]) {
+			return false;
+		}
+
+		// cards that need a target can't be played without one, the selection could never be finished
+		let pending = match self.hand[idx] {
+			CardType::Move => Some((PendingAction::Move, self.move_targets())),
+			CardType::Key => Some((PendingAction::Key, self.key_targets())),
+			CardType::Attack(attack) => Some((PendingAction::Attack(attack), self.attack_targets(attack))),
+			_ => None,
+		};
+
+		if let Some((_, targets)) = &pending {
+			if targets.is_empty() {
+				return false;
+			}
+		}
+
+		let card = self.hand.remove(idx);
+		self.discard_pile.push(card);
+
+		if self.uses_energy() {
+			self.energy -= self.card_cost(card);
+		}
+
+		match card {
+			CardType::Health => self.heal(1.0),
+			CardType::Armor => self.add_armor(1.0),
+			_ => (),
+		}
+
+		if let Some((action, targets)) = pending {
+			self.pending = Some(action);
+			self.targets = targets;
+		}
+
+		// with energy the hand is only refilled at the end of the turn
+		if !self.uses_energy() {
+			self.draw_cards();
+		}
+
+		true
+	}
+
+	// None if the action isn't allowed right now
+	// without energy the turn ends once the action is finished, a card that waits for a target isn't yet
+	pub fn perform(self: &mut Rules, action: Action) -> Option<Outcome> {
+		let outcome = match action {
+			Action::PlayCard(idx) => {
+				let card = *self.hand.get(idx)?;
+				self.play_card(idx).then_some(Outcome::CardPlayed(card))?
+			},
+			Action::ChooseTarget(pos) => self.choose_target(pos)?,
+			Action::EndTurn => {
+				if !self.can_end_turn() {
+					return None;
+				}
+
+				self.end_turn();
+				return Some(Outcome::TurnEnded);
+			},
+		};
+
+		if !self.uses_energy() && self.pending.is_none() && !self.completed {
+			self.end_turn();
+		}
+
+		Some(outcome)
+	}
+
+	pub fn can_end_turn(self: &Rules) -> bool {
+		self.uses_energy() && self.pending.is_none() && !self.completed && !self.is_over()
+	}
+
+	// the end of turn phase: the enemies act, then the next turn starts with a full hand and full energy
+	fn end_turn(self: &mut Rules) {
+		self.enemy_turn();
+		self.draw_cards();
+
+		self.turn += 1;
+		if let Some(energy) = self.energy_per_turn {
+			self.energy = energy;
+		}
+	}
+
+	pub fn enemy_at(self: &Rules, pos: (i32, i32)) -> bool {
+		self.enemies.iter().any(|enemy| enemy.pos == pos)
+	}
+
+	// every enemy acts on the state the ones before it left behind
+	pub fn enemy_turn(self: &mut Rules) {
+		for i in 0..self.enemies.len() {
+			if self.is_defeated() {
+				return;
+			}
+
+			match self.enemies[i].intent(self) {
+				Intent::Wait => (),
+				Intent::Move(pos) => self.enemies[i].pos = pos,
+				Intent::Attack(_) => {
+					self.damage(self.enemies[i].attack);
+				},
+				Intent::Strike(pattern) => {
+					let enemy = &self.enemies[i];
+					if pattern.fields(enemy.pos, enemy.range).contains(&self.player_pos) {
+						self.damage(enemy.attack);
+					}
+				},
+			}
+
+			self.enemies[i].turn += 1;
+		}
+	}
+
+	// every field an enemy would attack in the next enemy turn
+	pub fn danger_zone(self: &Rules) -> Vec<(i32, i32)> {
+		let mut fields: Vec<(i32, i32)> = self.enemies.iter().flat_map(|enemy| enemy.threatened(self)).collect();
+		fields.sort();
+		fields.dedup();
+
+		fields
+	}
+
+	// whether the card at idx could be played right now
+	pub fn can_play(self: &Rules, idx: usize) -> bool {
+		if idx >= self.hand.len() || self.pending.is_some() || self.is_over() || !self.can_afford(self.hand[idx]) {
+			return false;
+		}
+
+		self.has_targets(self.hand[idx])
+	}
+
+	// cards that need a target have at least one, the others always do
+	fn has_targets(self: &Rules, card: CardType) -> bool {
+		match card {
+			CardType::Move => !self.move_targets().is_empty(),
+			CardType::Key => !self.key_targets().is_empty(),
+			CardType::Attack(attack) => !self.attack_targets(attack).is_empty(),
+			_ => true,
+		}
+	}
+
+	// no card can be played and nothing is pending, the level can't be finished anymore
+	// with energy ending the turn might still help, unless no card is left to draw and none in the hand
+	// is ever affordable with a target, only enemies walking out of the way can still free a field to move to
+	pub fn is_stuck(self: &Rules) -> bool {
+		if self.completed || self.pending.is_some() || (0..self.hand.len()).any(|idx| self.can_play(idx)) {
+			return false;
+		}
+
+		match self.energy_per_turn {
+			Some(energy) if !self.is_over() => self.draw_pile.is_empty() && !self.hand.iter().any(|card| {
+				self.card_cost(*card) <= energy && (self.has_targets(*card) || (*card == CardType::Move && !self.enemies.is_empty()))
+			}),
+			_ => true,
+		}
+	}
+
+	// resolves the pending action on pos, if pos is one of the targets
+	pub fn choose_target(self: &mut Rules, pos: (i32, i32)) -> Option<Outcome> {
+		let action = self.pending?;
+
+		if !self.targets.contains(&pos) || self.is_over() {
+			return None;
+		}
+
+		self.pending = None;
+		self.targets.clear();
+
+		match action {
+			PendingAction::Move => {
+				self.player_pos = pos;
+				Some(Outcome::Moved(pos))
+			},
+			PendingAction::Key => {
+				self.completed = true;
+				Some(Outcome::ChestOpened(pos))
+			},
+			PendingAction::Attack(attack) => {
+				self.attack(attack, pos);
+				Some(Outcome::Attacked(pos))
+			},
+		}
+	}
+
+	pub fn breakable_at(self: &Rules, pos: (i32, i32)) -> bool {
+		self.breakables.iter().any(|breakable| breakable.pos == pos)
+	}
+
+	// trees, chests, enemies and breakables, nobody can walk onto these
+	fn is_blocked(self: &Rules, pos: (i32, i32)) -> bool {
+		self.grid.get(pos.0, pos.1) != Some(State::Empty) || self.enemy_at(pos) || self.breakable_at(pos)
+	}
+
+	// every breakable on the hit fields takes the player's attack, the broken ones are removed
+	// enemies have no health, attacks don't hit them
+	pub fn attack(self: &mut Rules, attack: AttackCard, target: (i32, i32)) {
+		let fields = self.attack_fields(attack, target);
+		let amount = self.attack_damage();
+
+		for breakable in self.breakables.iter_mut().filter(|breakable| fields.contains(&breakable.pos)) {
+			breakable.damage(amount);
+		}
+
+		self.breakables.retain(|breakable| !breakable.is_broken());
+	}
+
+	// the fields an attack aimed at target hits, never the player's own field
+	pub fn attack_fields(self: &Rules, attack: AttackCard, target: (i32, i32)) -> Vec<(i32, i32)> {
+		let (px, py) = self.player_pos;
+
+		let fields = match attack.shape {
+			AttackShape::Melee => vec!(target),
+			AttackShape::Line => {
+				let (dx, dy) = ((target.0 - px).signum(), (target.1 - py).signum());
+				let mut fields = vec!();
+
+				for distance in 1..=attack.range as i32 {
+					let pos = (px + dx * distance, py + dy * distance);
+					if self.grid.get(pos.0, pos.1) != Some(State::Empty) {
+						break;
+					}
+					fields.push(pos);
+				}
+
+				fields
+			},
+			AttackShape::Area => std::iter::once(target)
+				.chain(KEY_NEIGHBORS.iter().map(|(dx, dy)| (target.0 + dx, target.1 + dy)))
+				.collect(),
+		};
+
+		fields.into_iter()
+			.filter(|(x, y)| self.grid.in_bounds(*x, *y) && (*x, *y) != self.player_pos)
+			.collect()
+	}
+
+	// the fields an Attack card can be aimed at, only where it would hit a breakable
+	pub fn attack_targets(self: &Rules, attack: AttackCard) -> Vec<(i32, i32)> {
+		let (px, py) = self.player_pos;
+		let range = attack.range as i32;
+
+		let aims: Vec<(i32, i32)> = match attack.shape {
+			AttackShape::Line => KEY_NEIGHBORS.iter().map(|(dx, dy)| (px + dx, py + dy)).collect(),
+			AttackShape::Melee | AttackShape::Area => (-range..=range)
+				.flat_map(|dy| (-range..=range).map(move |dx| (dx, dy)))
+				.filter(|(dx, dy)| (*dx, *dy) != (0, 0) && dx.abs() + dy.abs() <= range)
+				.map(|(dx, dy)| (px + dx, py + dy))
+				.collect(),
+		};
+
+		aims.into_iter()
+			.filter(|(x, y)| self.grid.in_bounds(*x, *y))
+			.filter(|aim| self.attack_fields(attack, *aim).iter().any(|pos| self.breakable_at(*pos)))
+			.collect()
+	}
+
+	// every empty field in range without an enemy or a breakable, jumps over two fields need a free field in between
+	pub fn move_targets(self: &Rules) -> Vec<(i32, i32)> {
+		let (px, py) = self.player_pos;
+		let mut targets = vec!();
+
+		for neighbor in NEIGHBORS {
+			let Some((x, y)) = self.grid.neighbor(px, py, neighbor) else {
+				continue;
+			};
+
+			if neighbor.0 * neighbor.0 == 4 || neighbor.1 * neighbor.1 == 4 {
+				let (mx, my) = (neighbor.0 / 2 + px, neighbor.1 / 2 + py);
+				if self.is_blocked((mx, my)) {
+					continue;
+				}
+			}
+
+			if self.is_blocked((x, y)) {
+				continue;
+			}
+
+			targets.push((x, y));
+		}
+
+		targets
+	}
+
+	// every chest touching the player
+	pub fn key_targets(self: &Rules) -> Vec<(i32, i32)> {
+		let (px, py) = self.player_pos;
+
+		self.grid.neighbors(px, py, &KEY_NEIGHBORS)
+			.filter(|(x, y)| self.grid.get(*x, *y) == Some(State::Chest))
+			.collect()
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::breakable::BreakableKind;
+
+	// an empty grid with the player in the middle
+	fn rules_on(width: usize, height: usize) -> Rules {
+		let mut rules = Rules::new();
+		rules.grid = Grid::new(width, height);
+		rules.player_pos = (width as i32 / 2, height as i32 / 2);
+
+		rules
+	}
+
+	#[test]
+	fn move_targets_cover_the_whole_range() {
+		let rules = rules_on(5, 5);
+
+		let targets = rules.move_targets();
+		assert_eq!(targets.len(), NEIGHBORS.len());
+		assert!(targets.contains(&(2, 0)) && targets.contains(&(4, 2)) && targets.contains(&(1, 3)));
+	}
+
+	#[test]
+	fn move_targets_stay_on_the_grid() {
+		let mut rules = rules_on(5, 5);
+		rules.player_pos = (0, 0);
+
+		let mut targets = rules.move_targets();
+		targets.sort();
+		assert_eq!(targets, vec!((0, 1), (0, 2), (1, 0), (1, 1), (2, 0)));
+	}
+
+	#[test]
+	fn jumps_need_a_free_field_in_between() {
+		let mut rules = rules_on(5, 5);
+		rules.grid.set(2, 1, State::Tree);
+		rules.grid.set(3, 2, State::Chest);
+
+		let targets = rules.move_targets();
+		assert_eq!(targets.len(), NEIGHBORS.len() - 4);
+		for blocked in [(2, 1), (2, 0), (3, 2), (4, 2)] {
+			assert!(!targets.contains(&blocked), "{:?} shouldn't be a target", blocked);
+		}
+
+		// diagonals and the other jumps don't care
+		assert!(targets.contains(&(3, 1)) && targets.contains(&(2, 4)) && targets.contains(&(0, 2)));
+	}
+
+	#[test]
+	fn key_targets_are_the_touching_chests() {
+		let mut rules = rules_on(5, 5);
+		rules.grid.set(2, 3, State::Chest);
+		rules.grid.set(3, 3, State::Chest);
+		rules.grid.set(2, 0, State::Chest);
+
+		assert_eq!(rules.key_targets(), vec!((2, 3)));
+	}
+
+	#[test]
+	fn armor_takes_the_damage_before_health() {
+		let mut rules = rules_on(3, 3);
+		rules.values = [1.0, 2.0, 3.0];
+
+		assert_eq!(rules.damage(1.5), Hit { absorbed: 1.5, taken: 0.0, defeated: false });
+		assert_eq!(rules.damage(2.0), Hit { absorbed: 0.5, taken: 1.5, defeated: false });
+		assert_eq!((rules.value(ValueType::Armor), rules.value(ValueType::Health)), (0.0, 1.5));
+
+		// health never goes below zero
+		assert_eq!(rules.damage(5.0), Hit { absorbed: 0.0, taken: 1.5, defeated: true });
+		assert!(rules.is_defeated());
+	}
+
+	#[test]
+	fn values_stay_between_zero_and_the_maximum() {
+		let mut rules = rules_on(3, 3);
+		rules.values = [1.0, 2.0, 2.5];
+
+		rules.heal(1.0);
+		rules.add_armor(2.0);
+		assert_eq!(rules.value(ValueType::Health), MAX_VALUE);
+		assert_eq!(rules.value(ValueType::Armor), MAX_VALUE);
+
+		// negative amounts do nothing instead of hurting
+		rules.heal(-1.0);
+		rules.damage(-1.0);
+		assert_eq!(rules.value(ValueType::Health), MAX_VALUE);
+	}
+
+	#[test]
+	fn a_defeated_player_can_not_play() {
+		let mut rules = rules_on(3, 3);
+		rules.hand = vec!(CardType::Health);
+		rules.damage(rules.value(ValueType::Armor) + rules.value(ValueType::Health));
+
+		assert!(!rules.play_card(0));
+		assert!(rules.is_stuck());
+	}
+
+	#[test]
+	fn the_hand_is_refilled_from_the_top_of_the_draw_pile() {
+		let mut rules = rules_on(5, 5);
+		rules.hand_size = 2;
+		rules.draw_pile = vec!(CardType::Key, CardType::Health, CardType::Armor);
+
+		rules.draw_cards();
+		assert_eq!(rules.hand, vec!(CardType::Armor, CardType::Health));
+
+		assert!(rules.play_card(0));
+		assert_eq!(rules.hand, vec!(CardType::Health, CardType::Key));
+		assert_eq!(rules.discard_pile, vec!(CardType::Armor));
+
+		// discarded cards don't come back, the hand just gets smaller
+		assert!(rules.play_card(0));
+		assert_eq!(rules.hand, vec!(CardType::Key));
+		assert_eq!(rules.discard_pile, vec!(CardType::Armor, CardType::Health));
+		assert!(rules.draw_pile.is_empty());
+	}
+
+	#[test]
+	fn played_cards_wait_for_a_target() {
+		let mut rules = rules_on(5, 5);
+		rules.hand = vec!(CardType::Move, CardType::Key);
+		rules.hand_size = 2;
+		rules.draw_pile = vec!(CardType::Health);
+
+		assert!(rules.play_card(0));
+		assert_eq!(rules.pending, Some(PendingAction::Move));
+		assert_eq!(rules.targets, rules.move_targets());
+		assert_eq!(rules.hand, vec!(CardType::Key, CardType::Health));
+		assert_eq!(rules.discard_pile, vec!(CardType::Move));
+
+		// nothing else until the target is chosen
+		assert!(!rules.play_card(1));
+		assert_eq!(rules.choose_target((0, 0)), None);
+
+		assert_eq!(rules.choose_target((2, 4)), Some(Outcome::Moved((2, 4))));
+		assert_eq!(rules.player_pos, (2, 4));
+		assert_eq!(rules.pending, None);
+		assert!(rules.targets.is_empty());
+	}
+
+	#[test]
+	fn the_key_opens_a_touching_chest() {
+		let mut rules = rules_on(5, 5);
+		rules.hand = vec!(CardType::Key);
+
+		// without a chest the selection could never be finished
+		assert!(!rules.can_play(0));
+		assert!(!rules.play_card(0));
+		assert_eq!(rules.hand, vec!(CardType::Key));
+
+		rules.grid.set(1, 2, State::Chest);
+		assert!(rules.play_card(0));
+		assert_eq!(rules.choose_target((1, 2)), Some(Outcome::ChestOpened((1, 2))));
+		assert!(rules.completed);
+	}
+
+	#[test]
+	fn card_names_keep_the_attack_shape() {
+		let line = CardType::Attack(AttackCard { shape: AttackShape::Line, range: 3 });
+		assert_eq!(line.to_string(), "Attack Line 3");
+		assert_eq!(CardType::from_name("attack line 3"), Ok(line));
+		assert_eq!(serde_json::to_string(&line).unwrap(), "\"Attack Line 3\"");
+
+		assert_eq!(CardType::Attack(AttackCard::MELEE).to_string(), "Attack");
+		assert_eq!(CardType::from_name("Attack"), Ok(CardType::Attack(AttackCard::MELEE)));
+		assert!(CardType::from_name("Key 2").is_err());
+		assert!(CardType::from_name("Attack Cone").is_err());
+	}
+
+	#[test]
+	fn attacks_hit_the_fields_of_their_shape() {
+		let mut rules = rules_on(7, 7);
+		rules.grid.set(3, 0, State::Tree);
+
+		assert_eq!(rules.attack_fields(AttackCard::MELEE, (3, 2)), vec!((3, 2)));
+
+		// lines stop at trees
+		let line = AttackCard { shape: AttackShape::Line, range: 3 };
+		assert_eq!(rules.attack_fields(line, (3, 2)), vec!((3, 2), (3, 1)));
+
+		// areas never hit the player
+		let area = AttackCard { shape: AttackShape::Area, range: 2 };
+		let mut fields = rules.attack_fields(area, (3, 4));
+		fields.sort();
+		assert_eq!(fields, vec!((2, 4), (3, 4), (3, 5), (4, 4)));
+	}
+
+	#[test]
+	fn attacks_are_only_aimed_where_they_hit_a_breakable() {
+		let mut rules = rules_on(7, 7);
+		let line = AttackCard { shape: AttackShape::Line, range: 3 };
+		assert!(rules.attack_targets(line).is_empty());
+
+		rules.breakables.push(Breakable { kind: BreakableKind::Crate, pos: (3, 0), health: 1.0, max_health: 1.0 });
+		assert_eq!(rules.attack_targets(line), vec!((3, 2)));
+		assert!(rules.attack_targets(AttackCard::MELEE).is_empty());
+	}
+
+	#[test]
+	fn attacks_break_the_breakables_they_hit() {
+		let mut rules = rules_on(5, 5);
+		let line = AttackCard { shape: AttackShape::Line, range: 2 };
+		rules.hand = vec!(CardType::Attack(line));
+		rules.breakables.push(Breakable { kind: BreakableKind::Dummy, pos: (2, 1), health: 4.0, max_health: 4.0 });
+		rules.breakables.push(Breakable { kind: BreakableKind::Crate, pos: (2, 0), health: 1.0, max_health: 1.0 });
+
+		assert!(rules.play_card(0));
+		assert_eq!(rules.pending, Some(PendingAction::Attack(line)));
+		assert_eq!(rules.choose_target((2, 1)), Some(Outcome::Attacked((2, 1))));
+
+		// the dummy takes the attack value and stays, the crate breaks
+		assert_eq!(rules.breakables.len(), 1);
+		assert_eq!(rules.breakables[0].health, 4.0 - rules.attack_damage());
+	}
+
+	#[test]
+	fn a_plain_attack_cost_counts_for_every_attack() {
+		let mut rules = Rules::new();
+		let line = CardType::Attack(AttackCard { shape: AttackShape::Line, range: 3 });
+		assert_eq!(rules.card_cost(line), line.default_cost());
+
+		rules.card_costs.insert(CardType::Attack(AttackCard::MELEE), 2);
+		assert_eq!(rules.card_cost(line), 2);
+
+		rules.card_costs.insert(line, 3);
+		assert_eq!(rules.card_cost(line), 3);
+		assert_eq!(rules.card_cost(CardType::Attack(AttackCard::MELEE)), 2);
+	}
+
+	// two energy per turn, Health costs both
+	fn energy_rules() -> Rules {
+		let mut rules = rules_on(5, 5);
+		rules.energy_per_turn = Some(2);
+		rules.energy = 2;
+		rules.hand_size = 2;
+
+		rules
+	}
+
+	#[test]
+	fn cards_cost_energy_until_the_turn_ends() {
+		let mut rules = energy_rules();
+		rules.hand = vec!(CardType::Armor, CardType::Health);
+		rules.draw_pile = vec!(CardType::Armor);
+
+		assert_eq!(rules.perform(Action::PlayCard(0)), Some(Outcome::CardPlayed(CardType::Armor)));
+		assert_eq!(rules.energy, 1);
+		assert_eq!(rules.hand, vec!(CardType::Health));
+		assert_eq!(rules.turn, 1);
+
+		// not enough left for Health, but the turn can be ended
+		assert!(!rules.can_play(0));
+		assert_eq!(rules.perform(Action::PlayCard(0)), None);
+		assert!(rules.can_end_turn());
+		assert!(!rules.is_stuck());
+
+		assert_eq!(rules.perform(Action::EndTurn), Some(Outcome::TurnEnded));
+		assert_eq!(rules.turn, 2);
+		assert_eq!(rules.energy, 2);
+		assert_eq!(rules.hand, vec!(CardType::Health, CardType::Armor));
+	}
+
+	#[test]
+	fn without_energy_every_action_ends_the_turn() {
+		let mut rules = rules_on(5, 5);
+		rules.hand = vec!(CardType::Armor);
+
+		assert!(!rules.can_end_turn());
+		assert_eq!(rules.perform(Action::EndTurn), None);
+		assert_eq!(rules.perform(Action::PlayCard(0)), Some(Outcome::CardPlayed(CardType::Armor)));
+		assert_eq!(rules.turn, 2);
+	}
+
+	#[test]
+	fn the_turn_limit_ends_the_level() {
+		let mut rules = energy_rules();
+		rules.turn_limit = Some(1);
+		rules.hand = vec!(CardType::Armor);
+
+		assert_eq!(rules.perform(Action::EndTurn), Some(Outcome::TurnEnded));
+		assert!(rules.is_out_of_turns());
+		assert!(!rules.can_end_turn());
+		assert!(!rules.play_card(0));
+		assert!(rules.is_stuck());
+	}
+
+	#[test]
+	fn stuck_with_energy_needs_an_affordable_card_with_a_target() {
+		let mut rules = energy_rules();
+		rules.energy = 0;
+
+		// Health costs more than a whole turn gives
+		rules.card_costs.insert(CardType::Health, 3);
+		rules.hand = vec!(CardType::Health);
+		assert!(rules.is_stuck());
+
+		// the next turn gives enough energy for the key, but there is no chest
+		rules.hand = vec!(CardType::Health, CardType::Key);
+		assert!(rules.is_stuck());
+
+		rules.grid.set(2, 3, State::Chest);
+		assert!(!rules.is_stuck());
+
+		// cards left to draw might still help
+		rules.grid.set(2, 3, State::Empty);
+		rules.draw_pile = vec!(CardType::Move);
+		assert!(!rules.is_stuck());
+	}
+}
@@ -0,0 +1,275 @@
+// what is kept between runs: finished levels, best scores, seen tutorials and settings
+
+use serde::{Serialize, Deserialize};
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::rules::CardType;
+
+
+pub const SAVE_VERSION: u32 = 1;
+pub const SAVE_FILE: &str = "save.json";
+
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Settings {
+	pub fades: bool,				// fade to black between scenes
+	pub show_hints: bool,			// show the hint of a level right away
+}
+
+impl Default for Settings {
+	fn default() -> Self {
+		Settings {
+			fades: true,
+			show_hints: false,
+		}
+	}
+}
+
+// why a save game couldn't be read, only a broken one may be replaced by a new game
+#[derive(Debug)]
+pub enum SaveError {
+	Broken(serde_json::Error),		// not a save game (anymore)
+	Newer(u32),						// written by a newer version of the game
+	Io(io::Error),					// e.g. no permission, the file itself might be fine
+}
+
+impl fmt::Display for SaveError {
+	fn fmt(self: &SaveError, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			SaveError::Broken(e) => write!(f, "{}", e),
+			SaveError::Newer(version) => write!(f, "the save game has version {}, but this game only knows up to {}", version, SAVE_VERSION),
+			SaveError::Io(e) => write!(f, "{}", e),
+		}
+	}
+}
+
+impl Error for SaveError {}
+
+// levels are stored by their path, written the same way on every platform
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct SaveGame {
+	pub version: u32,
+	pub completed: BTreeSet<String>,
+	pub best_scores: BTreeMap<String, u32>,		// fewest cards played to open the chest
+	pub explained: Vec<CardType>,
+	pub last_level: Option<String>,				// the level select starts there
+	pub settings: Settings,
+}
+
+impl Default for SaveGame {
+	fn default() -> Self {
+		SaveGame {
+			version: SAVE_VERSION,
+			completed: BTreeSet::new(),
+			best_scores: BTreeMap::new(),
+			explained: vec!(),
+			last_level: None,
+			settings: Settings::default(),
+		}
+	}
+}
+
+pub fn level_key<P: AsRef<Path>>(path: P) -> String {
+	path.as_ref().to_string_lossy().replace('\\', "/")
+}
+
+impl SaveGame {
+	pub fn is_completed<P: AsRef<Path>>(self: &SaveGame, path: P) -> bool {
+		self.completed.contains(&level_key(path))
+	}
+
+	pub fn best_score<P: AsRef<Path>>(self: &SaveGame, path: P) -> Option<u32> {
+		self.best_scores.get(&level_key(path)).copied()
+	}
+
+	// returns true if the score is a new best
+	pub fn complete_level<P: AsRef<Path>>(self: &mut SaveGame, path: P, cards_played: u32) -> bool {
+		let key = level_key(path);
+		self.completed.insert(key.clone());
+
+		match self.best_scores.get(&key) {
+			Some(best) if *best <= cards_played => false,
+			_ => {
+				self.best_scores.insert(key, cards_played);
+				true
+			},
+		}
+	}
+}
+
+// a missing file is a new game, a file from a newer version of the game is an error
+pub fn parse_save(text: &str) -> Result<SaveGame, SaveError> {
+	let save: SaveGame = serde_json::from_str(text).map_err(SaveError::Broken)?;
+
+	if save.version > SAVE_VERSION {
+		return Err(SaveError::Newer(save.version));
+	}
+
+	// older versions are upgraded here once there are any
+	Ok(SaveGame { version: SAVE_VERSION, ..save })
+}
+
+pub fn read_save<P: AsRef<Path>>(path: P) -> Result<SaveGame, SaveError> {
+	if !path.as_ref().exists() {
+		return Ok(SaveGame::default());
+	}
+
+	parse_save(&fs::read_to_string(path).map_err(SaveError::Io)?)
+}
+
+// broken saves are moved aside instead of being overwritten, so they can still be rescued,
+// every one gets its own name, an older broken save is never replaced
+// a save of a newer version or one that couldn't be read stays where it is, the error comes back with
+// a new game then and the save must not be written, it would overwrite the player's progress
+pub fn read_save_or_default<P: AsRef<Path>>(path: P) -> (SaveGame, Option<SaveError>) {
+	let path = path.as_ref();
+
+	match read_save(path) {
+		Ok(save) => (save, None),
+		Err(SaveError::Broken(e)) => {
+			let broken = free_broken_path(path);
+			eprintln!("couldn't read the save game {}: {}, moving it to {}", path.display(), e, broken.display());
+
+			match fs::rename(path, &broken) {
+				Ok(()) => (SaveGame::default(), None),
+				Err(e) => (SaveGame::default(), Some(SaveError::Io(e))),
+			}
+		},
+		Err(e) => {
+			eprintln!("couldn't read the save game {}: {}", path.display(), e);
+			(SaveGame::default(), Some(e))
+		},
+	}
+}
+
+// save.json.broken for the first one, then save.json.broken.2 and so on
+fn broken_path(path: &Path, n: u32) -> PathBuf {
+	let mut name = path.file_name().unwrap_or_default().to_os_string();
+	name.push(".broken");
+	if n > 1 {
+		name.push(format!(".{}", n));
+	}
+	path.with_file_name(name)
+}
+
+fn free_broken_path(path: &Path) -> PathBuf {
+	(1..).map(|n| broken_path(path, n)).find(|broken| !broken.exists()).unwrap()
+}
+
+// writes a temporary file next to the save and renames it over the old one,
+// a crash in the middle leaves either the old or the new save, never half of one
+pub fn write_save<P: AsRef<Path>>(path: P, save: &SaveGame) -> Result<(), Box<dyn Error>> {
+	let path = path.as_ref();
+
+	if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+		fs::create_dir_all(dir)?;
+	}
+
+	let mut tmp_name = path.file_name().ok_or("the save game path has no file name")?.to_os_string();
+	tmp_name.push(".tmp");
+	let tmp_path = path.with_file_name(tmp_name);
+
+	let mut file = File::create(&tmp_path)?;
+	file.write_all(serde_json::to_string_pretty(save)?.as_bytes())?;
+	file.sync_all()?;
+	drop(file);
+
+	fs::rename(&tmp_path, path)?;
+
+	Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// a fresh directory for every test, they run at the same time
+	fn test_dir(name: &str) -> PathBuf {
+		let dir = std::env::temp_dir().join(format!("nordic_grid_{}_{}", name, std::process::id()));
+		let _ = fs::remove_dir_all(&dir);
+		fs::create_dir_all(&dir).unwrap();
+
+		dir
+	}
+
+	#[test]
+	fn saves_round_trip() {
+		let path = test_dir("round_trip").join(SAVE_FILE);
+
+		let mut save = SaveGame::default();
+		assert!(save.complete_level("levels/basics/level1.json", 3));
+		assert!(!save.complete_level("levels/basics/level1.json", 4));
+		save.explained.push(CardType::Move);
+		write_save(&path, &save).unwrap();
+
+		let read = read_save(&path).unwrap();
+		assert!(read.is_completed("levels/basics/level1.json"));
+		assert_eq!(read.best_score("levels/basics/level1.json"), Some(3));
+		assert_eq!(read.explained, vec!(CardType::Move));
+	}
+
+	#[test]
+	fn a_missing_save_is_a_new_game() {
+		let (save, error) = read_save_or_default(test_dir("missing").join(SAVE_FILE));
+
+		assert!(error.is_none());
+		assert!(save.completed.is_empty());
+	}
+
+	#[test]
+	fn broken_saves_are_moved_aside() {
+		let path = test_dir("broken").join(SAVE_FILE);
+		fs::write(&path, "{ not json").unwrap();
+
+		let (_, error) = read_save_or_default(&path);
+		assert!(error.is_none());
+		assert!(!path.exists());
+		assert_eq!(fs::read_to_string(broken_path(&path, 1)).unwrap(), "{ not json");
+	}
+
+	#[test]
+	fn every_broken_save_is_kept() {
+		let path = test_dir("broken_twice").join(SAVE_FILE);
+
+		fs::write(&path, "{ not json").unwrap();
+		read_save_or_default(&path);
+		fs::write(&path, "{ still not json").unwrap();
+		let (_, error) = read_save_or_default(&path);
+
+		assert!(error.is_none());
+		assert_eq!(fs::read_to_string(broken_path(&path, 1)).unwrap(), "{ not json");
+		assert_eq!(fs::read_to_string(broken_path(&path, 2)).unwrap(), "{ still not json");
+	}
+
+	#[test]
+	fn newer_saves_are_left_alone() {
+		let path = test_dir("newer").join(SAVE_FILE);
+		let text = format!("{{ \"version\": {} }}", SAVE_VERSION + 1);
+		fs::write(&path, &text).unwrap();
+
+		let (_, error) = read_save_or_default(&path);
+		assert!(matches!(error, Some(SaveError::Newer(_))));
+		assert_eq!(fs::read_to_string(&path).unwrap(), text);
+		assert!(!broken_path(&path, 1).exists());
+	}
+
+	#[test]
+	fn unreadable_saves_are_left_alone() {
+		// a directory exists, but can't be read like a file
+		let path = test_dir("unreadable").join(SAVE_FILE);
+		fs::create_dir_all(&path).unwrap();
+
+		let (_, error) = read_save_or_default(&path);
+		assert!(matches!(error, Some(SaveError::Io(_))));
+		assert!(path.is_dir());
+	}
+}
@@ -0,0 +1,146 @@
+// which screen the game is on, scenes are stacked so overlays (pause, summaries) can sit on top of a level
+
+use ggez::{
+	graphics::*,
+	glam::*,
+	Context, GameResult
+};
+
+
+const FADE_TIME: f32 = 0.5;			// seconds for fading out and in again
+
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Scene {
+	Title,
+	LevelSelect,
+	Playing,
+	Editor,
+	Replay,				// a recorded play of the current level
+	Paused,
+	LevelComplete,
+	GameOver,
+}
+
+impl Scene {
+	// overlays are drawn on top of the scene below them, the others hide everything below
+	pub fn is_overlay(self: Scene) -> bool {
+		matches!(self, Scene::Paused | Scene::LevelComplete | Scene::GameOver)
+	}
+}
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum SceneChange {
+	Push(Scene),
+	Pop,
+	Reset(Scene),		// throws away the whole stack
+}
+
+// a change waits until the screen is black, then the new scene fades in
+struct Fade {
+	time: f32,
+	change: Option<SceneChange>,
+}
+
+pub struct SceneStack {
+	scenes: Vec<Scene>,
+	fade: Option<Fade>,
+	pub fades: bool,			// a setting, without fades changes happen in the next frame
+}
+
+impl SceneStack {
+	pub fn new(first: Scene) -> SceneStack {
+		SceneStack {
+			scenes: vec!(first),
+			fade: None,
+			fades: true,
+		}
+	}
+
+	pub fn top(self: &SceneStack) -> Scene {
+		*self.scenes.last().unwrap()
+	}
+
+	pub fn contains(self: &SceneStack, scene: Scene) -> bool {
+		self.scenes.contains(&scene)
+	}
+
+	// no input while fading, or a second change could sneak in
+	pub fn is_fading(self: &SceneStack) -> bool {
+		self.fade.is_some()
+	}
+
+	// the scenes that have to be drawn, from the bottom up
+	pub fn visible(self: &SceneStack) -> &[Scene] {
+		let first = self.scenes.iter().rposition(|scene| !scene.is_overlay()).unwrap_or(0);
+		&self.scenes[first..]
+	}
+
+	// applied right away, without a fade
+	pub fn apply(self: &mut SceneStack, change: SceneChange) {
+		match change {
+			SceneChange::Push(scene) => self.scenes.push(scene),
+			SceneChange::Pop => {
+				// the bottom scene always stays
+				if self.scenes.len() > 1 {
+					self.scenes.pop();
+				}
+			},
+			SceneChange::Reset(scene) => self.scenes = vec!(scene),
+		}
+	}
+
+	// starts fading out, the change is applied once the screen is black
+	pub fn fade_to(self: &mut SceneStack, change: SceneChange) {
+		if self.fade.is_none() {
+			let time = if self.fades { 0.0 } else { FADE_TIME };
+			self.fade = Some(Fade { time, change: Some(change) });
+		}
+	}
+
+	// returns the change in the frame it was applied, so the game can set up the new scene
+	pub fn update(self: &mut SceneStack, dt: f32) -> Option<SceneChange> {
+		let fade = self.fade.as_mut()?;
+		fade.time += dt;
+
+		let mut applied = None;
+
+		if fade.time >= FADE_TIME * 0.5 {
+			if let Some(change) = fade.change.take() {
+				applied = Some(change);
+			}
+		}
+
+		if fade.time >= FADE_TIME {
+			self.fade = None;
+		}
+
+		if let Some(change) = applied {
+			self.apply(change);
+		}
+
+		applied
+	}
+
+	// a black rect over everything, getting darker until the change and lighter after it
+	pub fn draw_fade(self: &SceneStack, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+		let Some(fade) = &self.fade else {
+			return Ok(());
+		};
+
+		let half = FADE_TIME * 0.5;
+		let alpha = if fade.time < half { fade.time / half } else { 1.0 - (fade.time - half) / half };
+
+		let (screen_w, screen_h) = ctx.gfx.drawable_size();
+
+		canvas.draw(
+			&Quad,
+			DrawParam::default()
+				.dest_rect(Rect::new(0.0, 0.0, screen_w, screen_h))
+				.color(Color::new(0.0, 0.0, 0.0, alpha.clamp(0.0, 1.0)))
+				.z(10000)
+		);
+
+		Ok(())
+	}
+}
@@ -0,0 +1,204 @@
+// the small menu screens: title, pause overlay, level complete summary and game over
+
+use ggez::{
+	graphics::*,
+	glam::*,
+	input::keyboard::{KeyCode, KeyInput},
+	Context, GameResult
+};
+
+use crate::global_constants as GlobConst;
+use crate::save_game::Settings;
+
+
+const OVERLAY_Z: i32 = 3000;		// above the level and its HUD
+
+
+// what the game has to do after a key was pressed on one of the screens
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum ScreenAction {
+	Nothing,
+	Start,
+	Resume,
+	Restart,
+	NextLevel,
+	LevelSelect,
+	Title,
+	Quit,
+	ToggleFades,
+	ToggleHints,
+	Undo,
+}
+
+// a big title and some lines of text in the middle of the screen, dimming what is below if asked to
+fn draw_menu(ctx: &mut Context, canvas: &mut Canvas, title: &str, lines: &[String], dim: bool) {
+	let (screen_w, screen_h) = ctx.gfx.drawable_size();
+
+	if dim {
+		canvas.draw(
+			&Quad,
+			DrawParam::default()
+				.dest_rect(Rect::new(0.0, 0.0, screen_w, screen_h))
+				.color(Color::new(0.0, 0.0, 0.0, 0.6))
+				.z(OVERLAY_Z)
+		);
+	}
+
+	let mut title_text = Text::new(title);
+	title_text.set_scale(16.0 * GlobConst::SCALE)
+		.set_layout(TextLayout::center());
+	canvas.draw(&title_text, DrawParam::default().dest(vec2(screen_w * 0.5, screen_h * 0.4)).z(OVERLAY_Z + 1));
+
+	let mut text = Text::new(lines.join("\n"));
+	text.set_layout(TextLayout { h_align: TextAlign::Middle, v_align: TextAlign::Begin });
+	canvas.draw(&text, DrawParam::default().dest(vec2(screen_w * 0.5, screen_h * 0.4 + 60.0)).z(OVERLAY_Z + 1));
+}
+
+
+pub struct TitleScreen;
+
+impl TitleScreen {
+	pub fn key_down(self: &TitleScreen, input: KeyInput) -> ScreenAction {
+		match input.keycode {
+			Some(KeyCode::Return) | Some(KeyCode::Space) => ScreenAction::Start,
+			Some(KeyCode::Escape) => ScreenAction::Quit,
+			Some(KeyCode::F) => ScreenAction::ToggleFades,
+			Some(KeyCode::H) => ScreenAction::ToggleHints,
+			_ => ScreenAction::Nothing,
+		}
+	}
+
+	// the settings can be changed right here, save_error is why the save game wasn't loaded
+	pub fn draw(self: &TitleScreen, ctx: &mut Context, canvas: &mut Canvas, settings: &Settings, save_error: Option<&str>) -> GameResult {
+		let on_off = |on: bool| if on { "ON" } else { "OFF" };
+
+		let mut lines = vec!(
+			"PRESS ENTER TO START".to_string(),
+			String::new(),
+			format!("F FADES: {}", on_off(settings.fades)),
+			format!("H SHOW HINTS: {}", on_off(settings.show_hints)),
+			String::new(),
+			"ESC QUITS".to_string(),
+		);
+
+		if let Some(error) = save_error {
+			lines.push(String::new());
+			lines.push(format!("COULDN'T LOAD THE SAVE GAME: {}", error.to_uppercase()));
+			lines.push("IT IS LEFT AS IT IS, PROGRESS OF THIS RUN WON'T BE SAVED".to_string());
+		}
+
+		draw_menu(ctx, canvas, "NORDIC GRID", &lines, false);
+		Ok(())
+	}
+}
+
+
+pub struct PauseScreen;
+
+impl PauseScreen {
+	pub fn key_down(self: &PauseScreen, input: KeyInput) -> ScreenAction {
+		match input.keycode {
+			Some(KeyCode::Escape) | Some(KeyCode::P) | Some(KeyCode::Return) => ScreenAction::Resume,
+			Some(KeyCode::R) => ScreenAction::Restart,
+			Some(KeyCode::L) => ScreenAction::LevelSelect,
+			Some(KeyCode::Q) => ScreenAction::Title,
+			_ => ScreenAction::Nothing,
+		}
+	}
+
+	pub fn draw(self: &PauseScreen, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+		let lines = [
+			"ESC RESUME".to_string(),
+			"R RESTART THE LEVEL".to_string(),
+			"L LEVEL SELECT".to_string(),
+			"Q TITLE SCREEN".to_string(),
+		];
+		draw_menu(ctx, canvas, "PAUSED", &lines, true);
+		Ok(())
+	}
+}
+
+
+// what is shown after a chest was opened
+pub struct LevelCompleteScreen {
+	pub title: String,
+	pub cards_played: usize,
+	pub par: Option<u32>,
+	pub best: Option<u32>,
+	pub new_best: bool,
+	pub last_level: bool,			// the pack is done, there is no next level
+}
+
+impl LevelCompleteScreen {
+	pub fn key_down(self: &LevelCompleteScreen, input: KeyInput) -> ScreenAction {
+		match input.keycode {
+			Some(KeyCode::Return) | Some(KeyCode::Space) if self.last_level => ScreenAction::LevelSelect,
+			Some(KeyCode::Return) | Some(KeyCode::Space) => ScreenAction::NextLevel,
+			Some(KeyCode::R) => ScreenAction::Restart,
+			Some(KeyCode::L) => ScreenAction::LevelSelect,
+			_ => ScreenAction::Nothing,
+		}
+	}
+
+	pub fn draw(self: &LevelCompleteScreen, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+		let mut lines = vec!(self.title.to_uppercase());
+
+		lines.push(match self.par {
+			Some(par) if self.cards_played as u32 <= par => format!("{} CARDS PLAYED, PAR {}: WELL DONE", self.cards_played, par),
+			Some(par) => format!("{} CARDS PLAYED, PAR {}", self.cards_played, par),
+			None => format!("{} CARDS PLAYED", self.cards_played),
+		});
+
+		match self.best {
+			_ if self.new_best => lines.push("NEW BEST".to_string()),
+			Some(best) => lines.push(format!("BEST: {}", best)),
+			None => (),
+		}
+
+		lines.push(String::new());
+		if self.last_level {
+			lines.push("PACK COMPLETE, ENTER FOR THE LEVEL SELECT".to_string());
+		} else {
+			lines.push("ENTER NEXT LEVEL".to_string());
+		}
+		lines.push("R PLAY AGAIN".to_string());
+		lines.push("L LEVEL SELECT".to_string());
+
+		draw_menu(ctx, canvas, "LEVEL COMPLETE", &lines, true);
+		Ok(())
+	}
+}
+
+
+pub struct GameOverScreen {
+	pub reason: String,
+	pub can_undo: bool,			// getting stuck can be taken back
+}
+
+impl GameOverScreen {
+	pub fn key_down(self: &GameOverScreen, input: KeyInput) -> ScreenAction {
+		match input.keycode {
+			Some(KeyCode::R) | Some(KeyCode::Return) | Some(KeyCode::Space) => ScreenAction::Restart,
+			Some(KeyCode::Z) if self.can_undo => ScreenAction::Undo,
+			Some(KeyCode::L) => ScreenAction::LevelSelect,
+			Some(KeyCode::Q) | Some(KeyCode::Escape) => ScreenAction::Title,
+			_ => ScreenAction::Nothing,
+		}
+	}
+
+	pub fn draw(self: &GameOverScreen, ctx: &mut Context, canvas: &mut Canvas) -> GameResult {
+		let mut lines = vec!(
+			self.reason.to_uppercase(),
+			String::new(),
+			"R TRY AGAIN".to_string(),
+		);
+		if self.can_undo {
+			lines.push("Z UNDO THE LAST STEP".to_string());
+		}
+		lines.push("L LEVEL SELECT".to_string());
+		lines.push("Q TITLE SCREEN".to_string());
+
+		draw_menu(ctx, canvas, "GAME OVER", &lines, true);
+		Ok(())
+	}
+}
@@ -0,0 +1,172 @@
+// a level in the middle of being played, saved exactly as it is, including a pending Move or Key selection
+// used to suspend and resume, to attach to bug reports and as a starting point for tests
+
+use serde::{Serialize, Deserialize};
+
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+
+use crate::level_manager::Level;
+use crate::rules::{Rules, MAX_VALUE};
+
+
+pub const SNAPSHOT_VERSION: u32 = 1;
+pub const SNAPSHOT_FILE: &str = "snapshot.json";
+
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Snapshot {
+	pub version: u32,
+	pub level_path: Option<String>,		// where the level came from, the level itself is stored too
+	pub level: Level,					// needed to restart, edited levels might not exist as a file
+	pub rules: Rules,
+}
+
+impl Snapshot {
+	pub fn new(level_path: Option<String>, level: &Level, rules: &Rules) -> Snapshot {
+		Snapshot {
+			version: SNAPSHOT_VERSION,
+			level_path,
+			level: level.clone(),
+			rules: rules.clone(),
+		}
+	}
+
+	// a snapshot from a file could point anywhere, every position has to be on the grid
+	fn check(self: &Snapshot) -> Result<(), Box<dyn Error>> {
+		let grid = &self.rules.grid;
+
+		if !grid.is_consistent() {
+			return Err("the grid doesn't have width * height tiles".into());
+		}
+
+		let (px, py) = self.rules.player_pos;
+		if !grid.in_bounds(px, py) {
+			return Err(format!("the player at ({}, {}) is outside of the grid", px, py).into());
+		}
+
+		if let Some((x, y)) = self.rules.targets.iter().find(|(x, y)| !grid.in_bounds(*x, *y)) {
+			return Err(format!("the target ({}, {}) is outside of the grid", x, y).into());
+		}
+
+		if let Some(enemy) = self.rules.enemies.iter().find(|enemy| !grid.in_bounds(enemy.pos.0, enemy.pos.1)) {
+			return Err(format!("the {:?} at ({}, {}) is outside of the grid", enemy.behaviour, enemy.pos.0, enemy.pos.1).into());
+		}
+
+		if let Some(breakable) = self.rules.breakables.iter().find(|breakable| !grid.in_bounds(breakable.pos.0, breakable.pos.1)) {
+			return Err(format!("the {:?} at ({}, {}) is outside of the grid", breakable.kind, breakable.pos.0, breakable.pos.1).into());
+		}
+
+		if self.rules.values.iter().any(|value| !(0.0..=MAX_VALUE).contains(value)) {
+			return Err(format!("the values {:?} have to be between 0 and {}", self.rules.values, MAX_VALUE).into());
+		}
+
+		if self.rules.pending.is_some() == self.rules.targets.is_empty() {
+			return Err("a pending action needs targets and targets need a pending action".into());
+		}
+
+		// nothing can be played while an action is pending, so its card is the last one played
+		if let Some(action) = self.rules.pending {
+			if self.rules.discard_pile.last() != Some(&action.card()) {
+				return Err(format!("the pending {:?} wasn't started by a played card", action).into());
+			}
+		}
+
+		self.level.check_size()
+	}
+}
+
+pub fn snapshot_to_json(snapshot: &Snapshot) -> String {
+	serde_json::to_string_pretty(snapshot).unwrap()
+}
+
+pub fn parse_snapshot(text: &str) -> Result<Snapshot, Box<dyn Error>> {
+	let snapshot: Snapshot = serde_json::from_str(text)?;
+
+	if snapshot.version != SNAPSHOT_VERSION {
+		return Err(format!("snapshot version {} isn't supported", snapshot.version).into());
+	}
+
+	snapshot.check()?;
+
+	Ok(snapshot)
+}
+
+pub fn read_snapshot<P: AsRef<Path>>(path: P) -> Result<Snapshot, Box<dyn Error>> {
+	parse_snapshot(&fs::read_to_string(path)?)
+}
+
+pub fn write_snapshot<P: AsRef<Path>>(path: P, snapshot: &Snapshot) -> Result<(), Box<dyn Error>> {
+	if let Some(dir) = path.as_ref().parent().filter(|dir| !dir.as_os_str().is_empty()) {
+		fs::create_dir_all(dir)?;
+	}
+
+	let mut file = File::create(path)?;
+	file.write_all(snapshot_to_json(snapshot).as_bytes())?;
+
+	Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::level_manager::parse_text_level;
+	use crate::rules::{PendingAction, Action};
+
+	const LEVEL: &str = "cards: Move, Armor, Key, Move, Attack\ncards_on_hand: 2\nenergy_per_turn: 3\nenemy: Chaser 4 0\nbreakable: Crate 2 2\n\n@....\n.....\n...TC\n";
+
+	// in the middle of a turn: Armor played, a Move waiting for its target and three cards left to draw
+	fn mid_level() -> Snapshot {
+		let level = parse_text_level(LEVEL).unwrap();
+		let mut rules = level.rules(0);
+
+		rules.perform(Action::PlayCard(1)).unwrap();
+		rules.perform(Action::PlayCard(0)).unwrap();
+		assert_eq!(rules.pending, Some(PendingAction::Move));
+
+		Snapshot::new(Some("levels/test.txt".to_string()), &level, &rules)
+	}
+
+	fn rejected(snapshot: &Snapshot) -> String {
+		parse_snapshot(&snapshot_to_json(snapshot)).unwrap_err().to_string()
+	}
+
+	#[test]
+	fn snapshots_restore_the_exact_rules() {
+		let snapshot = mid_level();
+		assert_eq!(snapshot.rules.energy, 1);
+		assert_eq!(snapshot.rules.draw_pile.len(), 3);
+		assert_eq!(snapshot.rules.enemies.len(), 1);
+
+		let restored = parse_snapshot(&snapshot_to_json(&snapshot)).unwrap();
+		assert_eq!(restored.rules, snapshot.rules);
+		assert_eq!(restored.level, snapshot.level);
+		assert_eq!(restored.level_path, snapshot.level_path);
+	}
+
+	#[test]
+	fn snapshots_outside_of_the_rules_are_rejected() {
+		let mut snapshot = mid_level();
+		snapshot.rules.player_pos = (5, 0);
+		assert_eq!(rejected(&snapshot), "the player at (5, 0) is outside of the grid");
+
+		let mut snapshot = mid_level();
+		snapshot.rules.values[1] = MAX_VALUE + 1.0;
+		assert!(rejected(&snapshot).starts_with("the values "));
+
+		let mut snapshot = mid_level();
+		snapshot.version = SNAPSHOT_VERSION + 1;
+		assert_eq!(rejected(&snapshot), format!("snapshot version {} isn't supported", SNAPSHOT_VERSION + 1));
+
+		let mut snapshot = mid_level();
+		snapshot.rules.discard_pile.pop();
+		assert_eq!(rejected(&snapshot), "the pending Move wasn't started by a played card");
+
+		let mut snapshot = mid_level();
+		snapshot.rules.targets.clear();
+		assert_eq!(rejected(&snapshot), "a pending action needs targets and targets need a pending action");
+	}
+}
@@ -0,0 +1,315 @@
+// searches all card plays of a level, using nothing but the Rules
+// identical cards in the hand are the same play: a hand of two Moves counts each move once, not once per card
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::rules::{Rules, Grid, CardType, PendingAction, Action};
+
+
+// levels with energy and no turn limit are only searched this far
+const MAX_TURNS: u32 = 20;
+
+
+// one card play, with the rune position that was picked if the card needs one
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Step {
+	pub card: Option<CardType>,			// None ends the turn
+	pub hand_index: Option<usize>,		// None if the card was already played when the search started
+	pub target: Option<(i32, i32)>,
+}
+
+#[derive(Debug)]
+pub struct Report {
+	pub shortest: Option<Vec<Step>>,	// the fewest card plays, None if the level can't be solved
+	pub solutions: u64,					// distinct sequences of card types, targets and ended turns that open a chest
+	pub states: usize,					// states visited by the search for the shortest solution
+}
+
+impl Report {
+	pub fn solvable(self: &Report) -> bool {
+		self.shortest.is_some()
+	}
+
+	// the length of the shortest solution, ending a turn isn't a card play
+	pub fn card_plays(self: &Report) -> Option<usize> {
+		self.shortest.as_ref().map(|steps| card_plays(steps))
+	}
+
+	// how often the shortest solution ends the turn
+	pub fn turns_ended(self: &Report) -> Option<usize> {
+		self.shortest.as_ref().map(|steps| steps.len() - card_plays(steps))
+	}
+}
+
+pub fn card_plays(steps: &[Step]) -> usize {
+	steps.iter().filter(|step| step.card.is_some()).count()
+}
+
+// everything that matters for the rules, the order of the hand doesn't
+#[derive(PartialEq, Eq, Hash)]
+struct StateKey {
+	player_pos: (i32, i32),
+	pending: Option<PendingAction>,
+	completed: bool,
+	hand: Vec<CardType>,
+	draw_pile: Vec<CardType>,
+	grid: Grid,
+	values: [u32; 3],
+	enemies: Vec<((i32, i32), usize)>,	// position and entry of the pattern, everything else about them never changes
+	breakables: Vec<((i32, i32), u32)>,	// position and health
+	turn: u32,							// only counts with energy or a turn limit, otherwise it's always 0
+	energy: u32,
+}
+
+fn key(rules: &Rules) -> StateKey {
+	let mut hand = rules.hand.clone();
+	hand.sort();
+
+	StateKey {
+		player_pos: rules.player_pos,
+		pending: rules.pending,
+		completed: rules.completed,
+		hand,
+		draw_pile: rules.draw_pile.clone(),
+		grid: rules.grid.clone(),
+		values: rules.values.map(f32::to_bits),
+		enemies: rules.enemies.iter().map(|enemy| (enemy.pos, enemy.turn % enemy.pattern.len().max(1))).collect(),
+		breakables: rules.breakables.iter().map(|breakable| (breakable.pos, breakable.health.to_bits())).collect(),
+		turn: if rules.uses_energy() || rules.turn_limit.is_some() { rules.turn } else { 0 },
+		energy: rules.energy,
+	}
+}
+
+// every state that is one card play or one ended turn away
+// the same card type is only played once, which card of a kind is used doesn't change the state
+pub fn successors(rules: &Rules) -> Vec<(Step, Rules)> {
+	let mut next = vec!();
+	let mut tried: Vec<CardType> = vec!();
+
+	// a defeated player can't do anything anymore, neither can one who ran out of turns
+	if rules.is_defeated() || rules.is_out_of_turns() {
+		return next;
+	}
+
+	// a snapshot can start in the middle of a play, then only the target is left to pick
+	if let Some(action) = rules.pending {
+		let card = action.card();
+
+		for target in rules.targets.clone() {
+			let mut chosen = rules.clone();
+			chosen.perform(Action::ChooseTarget(target));
+			next.push((Step { card: Some(card), hand_index: None, target: Some(target) }, chosen));
+		}
+
+		return next;
+	}
+
+	for (i, card) in rules.hand.iter().enumerate() {
+		if tried.contains(card) {
+			continue;
+		}
+		tried.push(*card);
+
+		// perform, so the enemies take their turns like in the game
+		let mut played = rules.clone();
+		if played.perform(Action::PlayCard(i)).is_none() {
+			continue;
+		}
+
+		if played.pending.is_none() {
+			next.push((Step { card: Some(*card), hand_index: Some(i), target: None }, played));
+			continue;
+		}
+
+		for target in played.targets.clone() {
+			let mut chosen = played.clone();
+			chosen.perform(Action::ChooseTarget(target));
+			next.push((Step { card: Some(*card), hand_index: Some(i), target: Some(target) }, chosen));
+		}
+	}
+
+	// ending the turn again and again without a limit would never stop
+	if rules.turn_limit.is_some() || rules.turn < MAX_TURNS {
+		let mut ended = rules.clone();
+		if ended.perform(Action::EndTurn).is_some() {
+			next.push((Step { card: None, hand_index: None, target: None }, ended));
+		}
+	}
+
+	next
+}
+
+// the steps that led to the visited state at idx
+fn path(visited: &[Visited], idx: usize) -> Vec<Step> {
+	let mut steps = vec!();
+	let mut parent = Some(idx);
+
+	while let Some(p) = parent {
+		match visited[p].parent {
+			Some((pp, step)) => {
+				steps.push(step);
+				parent = Some(pp);
+			},
+			None => parent = None,
+		}
+	}
+
+	steps.reverse();
+	steps
+}
+
+struct Visited {
+	rules: Rules,
+	parent: Option<(usize, Step)>,		// the index of the state it came from
+	plays: usize,
+}
+
+// breadth first by card plays, ending a turn costs nothing, so those states go to the front of the queue
+// returns the solution with the fewest card plays and how many states were visited
+fn shortest(start: &Rules) -> (Option<Vec<Step>>, usize) {
+	let mut visited: Vec<Visited> = vec!(Visited { rules: start.clone(), parent: None, plays: 0 });
+	let mut best: HashMap<StateKey, usize> = HashMap::new();		// the fewest plays a state was reached with
+	let mut queue: VecDeque<usize> = VecDeque::new();
+
+	best.insert(key(start), 0);
+	queue.push_back(0);
+
+	while let Some(idx) = queue.pop_front() {
+		let plays = visited[idx].plays;
+
+		// reached again with fewer plays after it was queued
+		if best.get(&key(&visited[idx].rules)).is_some_and(|fewest| *fewest < plays) {
+			continue;
+		}
+
+		if visited[idx].rules.completed {
+			return (Some(path(&visited, idx)), visited.len());
+		}
+
+		for (step, rules) in successors(&visited[idx].rules) {
+			let plays = plays + step.card.is_some() as usize;
+
+			let k = key(&rules);
+			if best.get(&k).is_some_and(|fewest| *fewest <= plays) {
+				continue;
+			}
+			best.insert(k, plays);
+
+			visited.push(Visited { rules, parent: Some((idx, step)), plays });
+			if step.card.is_some() {
+				queue.push_back(visited.len() - 1);
+			} else {
+				queue.push_front(visited.len() - 1);
+			}
+		}
+	}
+
+	(None, visited.len())
+}
+
+// cards are never drawn twice and turns only go up, so the states form a graph without cycles
+fn count_solutions(rules: &Rules, memo: &mut HashMap<StateKey, u64>) -> u64 {
+	let k = key(rules);
+	if let Some(count) = memo.get(&k) {
+		return *count;
+	}
+
+	let mut count: u64 = 0;
+	for (_, next) in successors(rules) {
+		let n = if next.completed { 1 } else { count_solutions(&next, memo) };
+		count = count.saturating_add(n);
+	}
+
+	memo.insert(k, count);
+	count
+}
+
+// only the shortest solution, without counting all of them like solve does, e.g. to reject generated levels quickly
+pub fn find_shortest(rules: &Rules) -> Option<Vec<Step>> {
+	if rules.completed {
+		return Some(vec!());
+	}
+
+	shortest(rules).0
+}
+
+pub fn solve(rules: &Rules) -> Report {
+	if rules.completed {
+		return Report { shortest: Some(vec!()), solutions: 1, states: 1 };
+	}
+
+	let (shortest, states) = shortest(rules);
+	let solutions = if shortest.is_some() { count_solutions(rules, &mut HashMap::new()) } else { 0 };
+
+	Report {
+		shortest,
+		solutions,
+		states,
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::level_manager::{read_level, read_manifest};
+	use crate::pack::PACKS_DIR;
+
+	use std::path::Path;
+
+	// a row of fields with the player on the left and the chest on the right
+	fn corridor(length: usize, hand: Vec<CardType>) -> Rules {
+		let mut rules = Rules::new();
+		rules.grid = Grid::new(length, 1);
+		rules.grid.set(length as i32 - 1, 0, crate::rules::State::Chest);
+		rules.hand_size = hand.len();
+		rules.hand = hand;
+
+		rules
+	}
+
+	#[test]
+	fn bundled_levels_are_solved_in_par() {
+		for pack in ["basics", "ambush", "training"] {
+			let dir = Path::new(PACKS_DIR).join(pack);
+			let manifest = read_manifest(dir.join("pack.json")).unwrap();
+
+			for entry in &manifest.levels {
+				let level = read_level(dir.join(&entry.file)).unwrap();
+				let report = solve(&level.rules(level.seed.unwrap_or(0)));
+
+				assert_eq!(report.card_plays(), entry.par.map(|par| par as usize), "{}/{}", pack, entry.file);
+			}
+		}
+	}
+
+	#[test]
+	fn ended_turns_are_not_card_plays() {
+		let dir = Path::new(PACKS_DIR).join("ambush");
+		let level = read_level(dir.join("level4.json")).unwrap();
+		let report = solve(&level.rules(0));
+
+		assert_eq!(report.card_plays(), Some(8));
+		assert_eq!(report.turns_ended(), Some(3));
+		assert_eq!(report.shortest.unwrap().len(), 11);
+	}
+
+	#[test]
+	fn identical_cards_count_once() {
+		let rules = corridor(4, vec!(CardType::Move, CardType::Move, CardType::Key));
+		let report = solve(&rules);
+
+		// jump next to the chest, or walk there in two moves, not once for every Move card
+		assert_eq!(report.card_plays(), Some(2));
+		assert_eq!(report.solutions, 2);
+	}
+
+	#[test]
+	fn unsolvable_levels_have_no_solution() {
+		let rules = corridor(5, vec!(CardType::Move, CardType::Key));
+		let report = solve(&rules);
+
+		assert!(!report.solvable());
+		assert_eq!(report.solutions, 0);
+	}
+}
@@ -0,0 +1,361 @@
+// imports maps made with the Tiled editor (.tmj)
+//
+// tiles and objects are recognized by their class (called type in older Tiled versions):
+// "tree", "chest" and "player" (or "spawn"), everything else is decoration and stays empty
+// objects of the classes "chaser", "guard", "ranged" and "figurine" are enemies, with the optional properties
+// attack, range and pattern ("Cross, Wait"), "dummy" and "crate" are breakables with an optional health
+// the deck comes from the custom map properties cards ("Move, Key, Attack Line 2"), cards_on_hand
+// and explain_on_startup, shuffle, seed, undo_limit, energy_per_turn, card_costs ("Health=1") and turn_limit are optional
+
+use serde::Deserialize;
+
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use crate::enemy::{Behaviour, EnemySpawn};
+use crate::breakable::{BreakableKind, BreakableSpawn};
+use crate::level_manager::{Level, Tile, parse_pattern, parse_card_costs, explained_card};
+use crate::rules::CardType;
+
+
+// the upper bits of a gid store flipping and rotation
+const GID_MASK: u32 = 0x1fff_ffff;
+
+
+#[derive(Deserialize, Debug)]
+struct TiledMap {
+	width: usize,
+	height: usize,
+	tilewidth: f32,
+	tileheight: f32,
+	#[serde(default)]
+	infinite: bool,
+	#[serde(default)]
+	layers: Vec<TiledLayer>,
+	#[serde(default)]
+	tilesets: Vec<TiledTileset>,
+	#[serde(default)]
+	properties: Vec<TiledProperty>,
+}
+
+#[derive(Deserialize, Debug)]
+struct TiledLayer {
+	#[serde(rename = "type")]
+	layer_type: String,
+	#[serde(default)]
+	name: String,
+	#[serde(default = "visible")]
+	visible: bool,
+	data: Option<serde_json::Value>,
+	#[serde(default)]
+	objects: Vec<TiledObject>,
+	#[serde(default)]
+	layers: Vec<TiledLayer>,			// only group layers have these
+}
+
+fn visible() -> bool {
+	true
+}
+
+#[derive(Deserialize, Debug)]
+struct TiledObject {
+	#[serde(default)]
+	name: String,
+	#[serde(default, rename = "type")]
+	object_type: String,
+	#[serde(default)]
+	class: String,
+	x: f32,
+	y: f32,
+	#[serde(default)]
+	width: f32,
+	#[serde(default)]
+	height: f32,
+	gid: Option<u32>,
+	#[serde(default)]
+	properties: Vec<TiledProperty>,
+}
+
+#[derive(Deserialize, Debug)]
+struct TiledTileset {
+	firstgid: u32,
+	source: Option<String>,				// external tilesets live in their own .tsj file
+	#[serde(default)]
+	tiles: Vec<TiledTile>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ExternalTileset {
+	#[serde(default)]
+	tiles: Vec<TiledTile>,
+}
+
+#[derive(Deserialize, Debug)]
+struct TiledTile {
+	id: u32,
+	#[serde(default, rename = "type")]
+	tile_type: String,
+	#[serde(default)]
+	class: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct TiledProperty {
+	name: String,
+	value: serde_json::Value,
+}
+
+
+// "tree", "chest" or "player", in whatever case the artist typed it
+fn tile_from_class(class: &str) -> Option<Tile> {
+	match class.trim().to_lowercase().as_str() {
+		"tree" => Some(Tile::Tree),
+		"chest" => Some(Tile::Chest),
+		"player" | "spawn" => Some(Tile::Player),
+		_ => None,
+	}
+}
+
+// newer Tiled versions write class, older ones type
+fn class_of<'a>(class: &'a str, old_type: &'a str) -> &'a str {
+	if class.is_empty() { old_type } else { class }
+}
+
+// gid -> Tile for every tile with a known class, external tilesets are read relative to dir
+fn tile_classes(tilesets: &[TiledTileset], dir: &Path) -> Result<HashMap<u32, Tile>, Box<dyn Error>> {
+	let mut classes = HashMap::new();
+
+	for tileset in tilesets {
+		let external;
+		let tiles = match &tileset.source {
+			Some(source) => {
+				let text = fs::read_to_string(dir.join(source)).map_err(|e| format!("tileset \"{}\": {}", source, e))?;
+				external = serde_json::from_str::<ExternalTileset>(&text).map_err(|e| format!("tileset \"{}\": {}", source, e))?;
+				&external.tiles
+			},
+			None => &tileset.tiles,
+		};
+
+		for tile in tiles {
+			if let Some(t) = tile_from_class(class_of(&tile.class, &tile.tile_type)) {
+				classes.insert(tileset.firstgid + tile.id, t);
+			}
+		}
+	}
+
+	Ok(classes)
+}
+
+fn cards_property(value: &serde_json::Value) -> Result<Vec<CardType>, Box<dyn Error>> {
+	let text = value.as_str().ok_or("the cards property has to be a string")?;
+
+	text.split(',')
+		.map(str::trim)
+		.filter(|name| !name.is_empty())
+		.map(|name| CardType::from_name(name).map_err(|e| format!("{} in the cards property", e).into()))
+		.collect()
+}
+
+// group layers are flattened, hidden layers are skipped
+fn flatten_layers<'a>(layers: &'a [TiledLayer], out: &mut Vec<&'a TiledLayer>) {
+	for layer in layers.iter().filter(|layer| layer.visible) {
+		if layer.layer_type == "group" {
+			flatten_layers(&layer.layers, out);
+		} else {
+			out.push(layer);
+		}
+	}
+}
+
+fn enemy_object(object: &TiledObject, behaviour: Behaviour, x: i32, y: i32) -> Result<EnemySpawn, Box<dyn Error>> {
+	let mut enemy = EnemySpawn { behaviour, x, y, attack: None, range: None, pattern: vec!() };
+
+	for property in &object.properties {
+		match property.name.as_str() {
+			"attack" => enemy.attack = Some(property.value.as_f64().ok_or_else(|| format!("attack of \"{}\" has to be a number", object.name))? as f32),
+			"range" => enemy.range = Some(property.value.as_u64().and_then(|n| u32::try_from(n).ok()).ok_or_else(|| format!("range of \"{}\" has to be an int", object.name))?),
+			"pattern" => enemy.pattern = parse_pattern(property.value.as_str().ok_or_else(|| format!("pattern of \"{}\" has to be a string", object.name))?)?,
+			_ => (),
+		}
+	}
+
+	Ok(enemy)
+}
+
+fn breakable_object(object: &TiledObject, kind: BreakableKind, x: i32, y: i32) -> Result<BreakableSpawn, Box<dyn Error>> {
+	let mut breakable = BreakableSpawn { kind, x, y, health: None };
+
+	if let Some(property) = object.properties.iter().find(|property| property.name == "health") {
+		breakable.health = Some(property.value.as_f64().ok_or_else(|| format!("health of \"{}\" has to be a number", object.name))? as f32);
+	}
+
+	Ok(breakable)
+}
+
+// dir is where external tilesets are looked up, usually the folder of the map
+pub fn parse_tiled_level(text: &str, dir: &Path) -> Result<Level, Box<dyn Error>> {
+	let map: TiledMap = serde_json::from_str(text)?;
+
+	if map.infinite {
+		return Err("infinite Tiled maps aren't supported".into());
+	}
+
+	let classes = tile_classes(&map.tilesets, dir)?;
+	let tile_of_gid = |gid: u32| classes.get(&(gid & GID_MASK)).copied();
+
+	let mut level = Level {
+		width: map.width,
+		height: map.height,
+		map: vec!(vec!(Tile::Empty; map.width); map.height),
+		cards: vec!(),
+		cards_on_hand: 0,
+		explain_on_startup: None,
+		shuffle: false,
+		seed: None,
+		undo_limit: None,
+		enemies: vec!(),
+		energy_per_turn: None,
+		card_costs: BTreeMap::new(),
+		turn_limit: None,
+		breakables: vec!(),
+	};
+
+	let mut layers = vec!();
+	flatten_layers(&map.layers, &mut layers);
+
+	// later layers are drawn on top, so they win, but decoration never clears a tile
+	for layer in layers {
+		match layer.layer_type.as_str() {
+			"tilelayer" => {
+				let data: Vec<u32> = match &layer.data {
+					Some(data @ serde_json::Value::Array(_)) => serde_json::from_value(data.clone())?,
+					_ => return Err(format!("layer \"{}\" has to be saved with CSV tile data", layer.name).into()),
+				};
+
+				if data.len() != map.width * map.height {
+					return Err(format!("layer \"{}\" has {} tiles, but the map is {}x{}", layer.name, data.len(), map.width, map.height).into());
+				}
+
+				for (i, gid) in data.into_iter().enumerate() {
+					if let Some(tile) = tile_of_gid(gid) {
+						level.map[i / map.width][i % map.width] = tile;
+					}
+				}
+			},
+			"objectgroup" => {
+				for object in &layer.objects {
+					let class = class_of(&object.class, &object.object_type);
+					let tile = tile_from_class(class)
+						.or_else(|| tile_from_class(&object.name))
+						.or_else(|| object.gid.and_then(tile_of_gid));
+					let behaviour = Behaviour::from_name(class).or_else(|| Behaviour::from_name(&object.name));
+					let kind = BreakableKind::from_name(class).or_else(|| BreakableKind::from_name(&object.name));
+
+					if tile.is_none() && behaviour.is_none() && kind.is_none() {
+						continue;
+					}
+
+					// tile objects hang from their bottom left corner, everything else from the top left
+					let center_y = if object.gid.is_some() { object.y - object.height / 2.0 } else { object.y + object.height / 2.0 };
+					let x = ((object.x + object.width / 2.0) / map.tilewidth).floor();
+					let y = (center_y / map.tileheight).floor();
+
+					if x < 0.0 || y < 0.0 || x as usize >= map.width || y as usize >= map.height {
+						return Err(format!("object \"{}\" in layer \"{}\" is outside of the map", object.name, layer.name).into());
+					}
+
+					match (behaviour, kind, tile) {
+						(Some(behaviour), _, _) => level.enemies.push(enemy_object(object, behaviour, x as i32, y as i32)?),
+						(None, Some(kind), _) => level.breakables.push(breakable_object(object, kind, x as i32, y as i32)?),
+						(None, None, Some(tile)) => level.map[y as usize][x as usize] = tile,
+						(None, None, None) => (),
+					}
+				}
+			},
+			_ => (),
+		}
+	}
+
+	let mut has_cards = false;
+	let mut has_hand = false;
+
+	for property in &map.properties {
+		let value = &property.value;
+
+		match property.name.as_str() {
+			"cards" => {
+				has_cards = true;
+				level.cards = cards_property(value)?;
+			},
+			"cards_on_hand" => {
+				has_hand = true;
+				level.cards_on_hand = value.as_u64().and_then(|n| u8::try_from(n).ok()).ok_or("cards_on_hand has to be a small int")?;
+			},
+			"explain_on_startup" => {
+				let name = value.as_str().ok_or("explain_on_startup has to be a string")?;
+				if !name.trim().is_empty() {
+					level.explain_on_startup = Some(explained_card(CardType::from_name(name).map_err(|e| format!("{} in explain_on_startup", e))?)?);
+				}
+			},
+			"shuffle" => level.shuffle = value.as_bool().ok_or("shuffle has to be a bool")?,
+			"seed" => level.seed = Some(value.as_u64().ok_or("seed has to be an int")?),
+			"undo_limit" => level.undo_limit = Some(value.as_u64().and_then(|n| u32::try_from(n).ok()).ok_or("undo_limit has to be an int")?),
+			"energy_per_turn" => level.energy_per_turn = Some(value.as_u64().and_then(|n| u32::try_from(n).ok()).ok_or("energy_per_turn has to be an int")?),
+			"card_costs" => level.card_costs = parse_card_costs(value.as_str().ok_or("card_costs has to be a string")?)?,
+			"turn_limit" => level.turn_limit = Some(value.as_u64().and_then(|n| u32::try_from(n).ok()).ok_or("turn_limit has to be an int")?),
+			_ => (),
+		}
+	}
+
+	if !has_cards || !has_hand {
+		return Err("the map needs the custom properties cards and cards_on_hand".into());
+	}
+
+	level.check_size()?;
+
+	Ok(level)
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::level_manager::{parse_level, read_level};
+
+	const FIXTURES: &str = "resources/fixtures/tiled";
+
+	// group layers, flipped gids, an external tileset, a hidden layer and tile, rect and point objects
+	#[test]
+	fn imports_the_same_level_as_the_json() {
+		let dir = Path::new(FIXTURES);
+		let imported = parse_tiled_level(&fs::read_to_string(dir.join("meadow.tmj")).unwrap(), dir).unwrap();
+		let expected = parse_level(&fs::read_to_string(dir.join("meadow.json")).unwrap()).unwrap();
+
+		assert_eq!(imported, expected);
+	}
+
+	#[test]
+	fn reads_tiled_maps_by_their_extension() {
+		let dir = Path::new(FIXTURES);
+
+		assert_eq!(read_level(dir.join("meadow.tmj")).unwrap(), read_level(dir.join("meadow.json")).unwrap());
+	}
+
+	#[test]
+	fn a_map_needs_a_deck() {
+		let text = "{ \"width\": 1, \"height\": 1, \"tilewidth\": 16, \"tileheight\": 16, \"layers\": [] }";
+
+		assert!(parse_tiled_level(text, Path::new(FIXTURES)).is_err());
+	}
+
+	#[test]
+	fn layers_have_to_fit_the_map() {
+		let text = "{ \"width\": 2, \"height\": 1, \"tilewidth\": 16, \"tileheight\": 16, \"layers\": [{ \"type\": \"tilelayer\", \"name\": \"short\", \"data\": [0] }],
+			\"properties\": [{ \"name\": \"cards\", \"value\": \"Key\" }, { \"name\": \"cards_on_hand\", \"value\": 1 }] }";
+
+		let error = parse_tiled_level(text, Path::new(FIXTURES)).unwrap_err();
+		assert!(error.to_string().contains("short"), "{}", error);
+	}
+}
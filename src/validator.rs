@@ -0,0 +1,491 @@
+// finds everything wrong with a level file instead of stopping at the first problem
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::level_manager::{Level, LevelV1, LevelV2, Manifest, Tile, card_type_from_name, level_version, parse_text_level, is_tiled_level, is_explained};
+use crate::enemy::Behaviour;
+use crate::rules::{CardType, AttackCard, card_cost};
+use crate::tiled::parse_tiled_level;
+
+
+pub struct Problem {
+	pub line: Option<usize>,		// 1 based, None if it's about the whole file
+	pub message: String,
+}
+
+impl Problem {
+	fn new(line: Option<usize>, message: String) -> Problem {
+		Problem { line, message }
+	}
+}
+
+// line lookups in the raw json (or text level), so problems can point at the right place
+struct Source<'a> {
+	lines: Vec<&'a str>,
+}
+
+impl<'a> Source<'a> {
+	fn new(text: &'a str) -> Source<'a> {
+		Source { lines: text.lines().collect() }
+	}
+
+	// line of "key": in json or key: in a text level header
+	fn key_line(self: &Source<'a>, key: &str) -> Option<usize> {
+		let quoted = format!("\"{}\"", key);
+		let header = format!("{}:", key);
+		self.lines.iter()
+			.position(|line| line.contains(&quoted) || line.starts_with(&header))
+			.map(|i| i + 1)
+	}
+
+	// line of the idx-th entry of the array behind "key", if every entry has its own line
+	fn item_line(self: &Source<'a>, key: &str, idx: usize) -> Option<usize> {
+		let start = self.key_line(key)?;
+
+		self.lines[start..].iter()
+			.enumerate()
+			.take_while(|(_, line)| !line.trim_start().starts_with(']'))
+			.filter(|(_, line)| {
+				let line = line.trim_start();
+				line.starts_with('[') || line.starts_with('{') || line.starts_with('"') || line.starts_with(|c: char| c.is_ascii_digit())
+			})
+			.nth(idx)
+			.map(|(i, _)| start + i + 1)
+			.or(Some(start))
+	}
+
+	// the idx-th enemy or breakable, text levels have one "enemy:" or "breakable:" line for each
+	fn spawn_line(self: &Source<'a>, header: &str, key: &str, idx: usize) -> Option<usize> {
+		self.lines.iter()
+			.enumerate()
+			.filter(|(_, line)| line.starts_with(header))
+			.nth(idx)
+			.map(|(i, _)| i + 1)
+			.or_else(|| self.item_line(key, idx))
+	}
+}
+
+pub fn validate_level_source(text: &str) -> Vec<Problem> {
+	let source = Source::new(text);
+	let mut problems = vec!();
+
+	let version = match level_version(text) {
+		Ok(version) => version,
+		Err(e) => return vec!(Problem::new(Some(e.line()), e.to_string())),
+	};
+
+	// unknown values are collected instead of stopping at the first one like parse_level does
+	let level = match version {
+		1 => match serde_json::from_str::<LevelV1>(text) {
+			Ok(raw) => lenient_v1(raw, &source, &mut problems),
+			Err(e) => return vec!(Problem::new(Some(e.line()), e.to_string())),
+		},
+		2 => match serde_json::from_str::<LevelV2>(text) {
+			Ok(raw) => lenient_v2(raw, &source, &mut problems),
+			Err(e) => return vec!(Problem::new(Some(e.line()), e.to_string())),
+		},
+		v => return vec!(Problem::new(source.key_line("version"), format!("unsupported level version {}", v))),
+	};
+
+	check_level(&level, &source, &mut problems);
+
+	problems
+}
+
+fn lenient_v1(raw: LevelV1, source: &Source, problems: &mut Vec<Problem>) -> Level {
+	let mut map = vec!();
+	for (y, row) in raw.map.iter().enumerate() {
+		let mut tiles = vec!();
+		for (x, code) in row.iter().enumerate() {
+			tiles.push(Tile::from_code(*code).unwrap_or_else(|| {
+				problems.push(Problem::new(source.item_line("map", y), format!("unknown tile code {} at ({}, {})", code, x, y)));
+				Tile::Empty
+			}));
+		}
+		map.push(tiles);
+	}
+
+	let mut cards = vec!();
+	for (i, card) in raw.cards.iter().enumerate() {
+		match card_type_from_name(card) {
+			Some(card_type) => cards.push(card_type),
+			None => problems.push(Problem::new(source.item_line("cards", i), format!("unknown card \"{}\"", card))),
+		}
+	}
+
+	let explain_on_startup = match raw.explain_on_startup.as_str() {
+		"" => None,
+		name => {
+			let card_type = card_type_from_name(name);
+			if card_type.is_none() {
+				problems.push(Problem::new(source.key_line("explain_on_startup"), format!("unknown card \"{}\" in explain_on_startup", name)));
+			}
+			card_type
+		}
+	};
+
+	let (width, height) = raw.size();
+
+	Level {
+		width,
+		height,
+		map,
+		cards,
+		cards_on_hand: raw.cards_on_hand,
+		explain_on_startup,
+		shuffle: raw.shuffle,
+		seed: raw.seed,
+		undo_limit: None,
+		enemies: vec!(),
+		energy_per_turn: None,
+		card_costs: BTreeMap::new(),
+		turn_limit: None,
+		breakables: vec!(),
+	}
+}
+
+fn lenient_v2(raw: LevelV2, source: &Source, problems: &mut Vec<Problem>) -> Level {
+	let mut map = vec!();
+	for (y, row) in raw.map.iter().enumerate() {
+		let mut tiles = vec!();
+		for (x, c) in row.chars().enumerate() {
+			tiles.push(raw.legend.get(&c).copied().unwrap_or_else(|| {
+				problems.push(Problem::new(source.item_line("map", y), format!("'{}' at ({}, {}) isn't in the legend", c, x, y)));
+				Tile::Empty
+			}));
+		}
+		map.push(tiles);
+	}
+
+	Level {
+		width: raw.width,
+		height: raw.height,
+		map,
+		cards: raw.cards,
+		cards_on_hand: raw.cards_on_hand,
+		explain_on_startup: raw.explain_on_startup,
+		shuffle: raw.shuffle,
+		seed: raw.seed,
+		undo_limit: raw.undo_limit,
+		enemies: raw.enemies,
+		energy_per_turn: raw.energy_per_turn,
+		card_costs: raw.card_costs,
+		turn_limit: raw.turn_limit,
+		breakables: raw.breakables,
+	}
+}
+
+// the checks that don't depend on the file version
+fn check_level(level: &Level, source: &Source, problems: &mut Vec<Problem>) {
+	// map dimensions
+	if level.width == 0 || level.height == 0 {
+		problems.push(Problem::new(source.key_line("width"), format!("level size {}x{} is empty", level.width, level.height)));
+	}
+
+	if level.map.len() != level.height {
+		problems.push(Problem::new(source.key_line("map"), format!("map has {} rows, but the level height is {}", level.map.len(), level.height)));
+	}
+
+	// tiles
+	let mut spawns = vec!();
+	let mut chests = 0;
+
+	for (y, row) in level.map.iter().enumerate() {
+		let line = source.item_line("map", y);
+
+		if row.len() != level.width {
+			problems.push(Problem::new(line, format!("row {} has {} tiles, but the level width is {}", y, row.len(), level.width)));
+		}
+
+		for (x, tile) in row.iter().enumerate() {
+			match tile {
+				Tile::Player => spawns.push((x, y, line)),
+				Tile::Chest => chests += 1,
+				_ => (),
+			}
+		}
+	}
+
+	match spawns.len() {
+		0 => problems.push(Problem::new(source.key_line("map"), "no player spawn".to_string())),
+		1 => (),
+		n => {
+			for (x, y, line) in &spawns {
+				problems.push(Problem::new(*line, format!("one of {} player spawns at ({}, {}), there has to be exactly one", n, x, y)));
+			}
+		}
+	}
+
+	if chests == 0 {
+		problems.push(Problem::new(source.key_line("map"), "no chest".to_string()));
+	}
+
+	// enemies
+	for (i, enemy) in level.enemies.iter().enumerate() {
+		let line = source.spawn_line("enemy:", "enemies", i);
+		let tile = if enemy.x >= 0 && enemy.y >= 0 {
+			level.map.get(enemy.y as usize).and_then(|row| row.get(enemy.x as usize))
+		} else {
+			None
+		};
+
+		match tile {
+			None => problems.push(Problem::new(line, format!("the {:?} at ({}, {}) is outside of the map", enemy.behaviour, enemy.x, enemy.y))),
+			Some(Tile::Empty) => (),
+			Some(tile) => problems.push(Problem::new(line, format!("the {:?} at ({}, {}) stands on a {:?}", enemy.behaviour, enemy.x, enemy.y, tile))),
+		}
+
+		if level.enemies[..i].iter().any(|other| (other.x, other.y) == (enemy.x, enemy.y)) {
+			problems.push(Problem::new(line, format!("more than one enemy at ({}, {})", enemy.x, enemy.y)));
+		}
+
+		if enemy.range == Some(0) {
+			problems.push(Problem::new(line, format!("the {:?} at ({}, {}) has a range of 0", enemy.behaviour, enemy.x, enemy.y)));
+		}
+
+		match (enemy.behaviour, enemy.pattern.is_empty()) {
+			(Behaviour::Figurine, true) => problems.push(Problem::new(line, format!("the Figurine at ({}, {}) has no pattern", enemy.x, enemy.y))),
+			(Behaviour::Figurine, false) | (_, true) => (),
+			(_, false) => problems.push(Problem::new(line, format!("the {:?} at ({}, {}) has a pattern, but only figurines use one", enemy.behaviour, enemy.x, enemy.y))),
+		}
+
+		if enemy.attack.is_some_and(|attack| attack < 0.0) {
+			problems.push(Problem::new(line, format!("the {:?} at ({}, {}) has a negative attack", enemy.behaviour, enemy.x, enemy.y)));
+		}
+	}
+
+	// breakables
+	for (i, breakable) in level.breakables.iter().enumerate() {
+		let line = source.spawn_line("breakable:", "breakables", i);
+		let tile = if breakable.x >= 0 && breakable.y >= 0 {
+			level.map.get(breakable.y as usize).and_then(|row| row.get(breakable.x as usize))
+		} else {
+			None
+		};
+
+		match tile {
+			None => problems.push(Problem::new(line, format!("the {:?} at ({}, {}) is outside of the map", breakable.kind, breakable.x, breakable.y))),
+			Some(Tile::Empty) => (),
+			Some(tile) => problems.push(Problem::new(line, format!("the {:?} at ({}, {}) stands on a {:?}", breakable.kind, breakable.x, breakable.y, tile))),
+		}
+
+		let taken = level.breakables[..i].iter().any(|other| (other.x, other.y) == (breakable.x, breakable.y))
+			|| level.enemies.iter().any(|enemy| (enemy.x, enemy.y) == (breakable.x, breakable.y));
+		if taken {
+			problems.push(Problem::new(line, format!("the {:?} at ({}, {}) shares its field", breakable.kind, breakable.x, breakable.y)));
+		}
+
+		if breakable.health.is_some_and(|health| health <= 0.0) {
+			problems.push(Problem::new(line, format!("the {:?} at ({}, {}) has no health", breakable.kind, breakable.x, breakable.y)));
+		}
+	}
+
+	let attacks: Vec<AttackCard> = level.cards.iter()
+		.filter_map(|card| match card {
+			CardType::Attack(attack) => Some(*attack),
+			_ => None,
+		})
+		.collect();
+
+	if let Some(attack) = attacks.iter().find(|attack| attack.range == 0) {
+		problems.push(Problem::new(source.key_line("cards"), format!("\"{}\" has a range of 0", CardType::Attack(*attack))));
+	}
+
+	if !attacks.is_empty() && level.breakables.is_empty() {
+		problems.push(Problem::new(source.key_line("cards"), "the deck has Attack cards, but nothing to attack".to_string()));
+	}
+
+	// cards
+	if level.cards_on_hand as usize > level.cards.len() {
+		problems.push(Problem::new(source.key_line("cards_on_hand"), format!("cards_on_hand is {}, but the deck only has {} cards", level.cards_on_hand, level.cards.len())));
+	}
+
+	if let Some(card_type) = level.explain_on_startup {
+		if !is_explained(card_type) {
+			problems.push(Problem::new(source.key_line("explain_on_startup"), format!("no explanation for {}", card_type)));
+		}
+	}
+
+	// turns
+	match level.energy_per_turn {
+		Some(0) => problems.push(Problem::new(source.key_line("energy_per_turn"), "energy_per_turn is 0, no card could ever be played".to_string())),
+		Some(energy) => {
+			let mut deck = level.cards.clone();
+			deck.sort();
+			deck.dedup();
+
+			for card in deck {
+				let cost = card_cost(&level.card_costs, card);
+				if cost > energy {
+					problems.push(Problem::new(source.key_line("energy_per_turn"), format!("{} costs {}, but a turn only has {} energy", card, cost, energy)));
+				}
+			}
+		},
+		None if !level.card_costs.is_empty() => problems.push(Problem::new(source.key_line("card_costs"), "card_costs only matter with energy_per_turn".to_string())),
+		None => (),
+	}
+
+	if level.turn_limit == Some(0) {
+		problems.push(Problem::new(source.key_line("turn_limit"), "turn_limit is 0, the level can't be finished".to_string()));
+	}
+}
+
+// text levels stop at the first syntax error, the rest is checked like json levels
+pub fn validate_text_level_source(text: &str) -> Vec<Problem> {
+	let source = Source::new(text);
+	let mut problems = vec!();
+
+	match parse_text_level(text) {
+		Ok(level) => check_level(&level, &source, &mut problems),
+		Err(e) => {
+			// errors look like "line 3: ...", the line is reported separately
+			let message = e.to_string();
+			let split = message.strip_prefix("line ")
+				.and_then(|rest| rest.split_once(": "))
+				.and_then(|(number, rest)| Some((number.parse().ok()?, rest.to_string())));
+
+			match split {
+				Some((line, rest)) => problems.push(Problem::new(Some(line), rest)),
+				None => problems.push(Problem::new(None, message)),
+			}
+		},
+	}
+
+	problems
+}
+
+// every listed level of a pack has to exist next to the manifest
+pub fn validate_manifest_source(text: &str, dir: &Path) -> Vec<Problem> {
+	let source = Source::new(text);
+
+	let manifest: Manifest = match serde_json::from_str(text) {
+		Ok(manifest) => manifest,
+		Err(e) => return vec!(Problem::new(Some(e.line()), e.to_string())),
+	};
+
+	let mut problems = vec!();
+
+	if manifest.levels.is_empty() {
+		problems.push(Problem::new(source.key_line("levels"), "the manifest doesn't list any levels".to_string()));
+	}
+
+	for (i, level) in manifest.levels.iter().enumerate() {
+		// levels with metadata have their file on a "file" line, old manifests only list the files
+		let line = source.lines.iter()
+			.enumerate()
+			.filter(|(_, line)| line.contains("\"file\""))
+			.nth(i)
+			.map(|(l, _)| l + 1)
+			.or_else(|| source.item_line("levels", i));
+
+		if !dir.join(&level.file).is_file() {
+			problems.push(Problem::new(line, format!("level \"{}\" doesn't exist", level.file)));
+		}
+
+		if level.par == Some(0) {
+			problems.push(Problem::new(line, format!("level \"{}\" has a par of 0 card plays", level.file)));
+		}
+	}
+
+	problems
+}
+
+// manifests are told apart from levels by their "levels" list
+pub fn validate_file<P: AsRef<Path>>(path: P) -> Vec<Problem> {
+	let text = match fs::read_to_string(&path) {
+		Ok(text) => text,
+		Err(e) => return vec!(Problem::new(None, e.to_string())),
+	};
+
+	if path.as_ref().extension().is_some_and(|ext| ext == "txt") {
+		return validate_text_level_source(&text);
+	}
+
+	// Tiled maps have no useful line numbers, their problems are about the whole file
+	if is_tiled_level(path.as_ref()) {
+		let dir = path.as_ref().parent().unwrap_or(Path::new(""));
+		let mut problems = vec!();
+
+		match parse_tiled_level(&text, dir) {
+			Ok(level) => check_level(&level, &Source::new(""), &mut problems),
+			Err(e) => problems.push(Problem::new(None, e.to_string())),
+		}
+
+		return problems;
+	}
+
+	let is_manifest = serde_json::from_str::<serde_json::Value>(&text)
+		.map(|value| value.get("levels").is_some() && value.get("map").is_none())
+		.unwrap_or(false);
+
+	if is_manifest {
+		let dir = path.as_ref().parent().unwrap_or(Path::new(""));
+		validate_manifest_source(&text, dir)
+	} else {
+		validate_level_source(&text)
+	}
+}
+
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	// a version 2 level laid out like the hand written ones, the map starts on line 7
+	fn level_json(map: &[&str], cards: &str, extra: &str) -> String {
+		let rows: Vec<String> = map.iter().map(|row| format!("\t\t\"{}\"", row)).collect();
+
+		format!("{{\n\t\"version\": 2,\n\t\"width\": {},\n\t\"height\": {},\n\t\"legend\": {{\".\": \"Empty\", \"@\": \"Player\", \"T\": \"Tree\", \"C\": \"Chest\"}},\n\t\"map\": [\n{}\n\t],\n\t\"cards\": [{}],\n{}\t\"cards_on_hand\": 1\n}}\n",
+			map[0].len(), map.len(), rows.join(",\n"), cards, extra)
+	}
+
+	fn problems(text: &str) -> Vec<(Option<usize>, String)> {
+		validate_level_source(text).into_iter().map(|problem| (problem.line, problem.message)).collect()
+	}
+
+	#[test]
+	fn a_good_level_has_no_problems() {
+		assert!(problems(&level_json(&["@.C"], "\"Key\"", "")).is_empty());
+	}
+
+	#[test]
+	fn the_map_needs_one_spawn_and_a_chest() {
+		assert_eq!(problems(&level_json(&["..C"], "\"Key\"", "")), vec!((Some(6), "no player spawn".to_string())));
+		assert_eq!(problems(&level_json(&["@.."], "\"Key\"", "")), vec!((Some(6), "no chest".to_string())));
+
+		// every spawn is reported on its own row
+		assert_eq!(problems(&level_json(&["@.C", "..@"], "\"Key\"", "")), vec!(
+			(Some(7), "one of 2 player spawns at (0, 0), there has to be exactly one".to_string()),
+			(Some(8), "one of 2 player spawns at (2, 1), there has to be exactly one".to_string()),
+		));
+	}
+
+	#[test]
+	fn unknown_cards_are_reported_on_their_line() {
+		let found = problems(&level_json(&["@.C"], "\"Key\", \"Sword\"", ""));
+		assert_eq!(found.len(), 1);
+		assert_eq!(found[0].0, Some(9));
+		assert!(found[0].1.contains("unknown card \"Sword\""), "{}", found[0].1);
+
+		// version 1 files keep going after an unknown card
+		let text = "{\n\t\"map\": [[1, 3]],\n\t\"cards\": [\n\t\t\"Key\",\n\t\t\"Sword\"\n\t],\n\t\"cards_on_hand\": 1\n}\n";
+		assert_eq!(problems(text), vec!((Some(5), "unknown card \"Sword\"".to_string())));
+	}
+
+	#[test]
+	fn the_hand_can_not_be_bigger_than_the_deck() {
+		let text = level_json(&["@.C"], "\"Key\"", "").replace("\"cards_on_hand\": 1", "\"cards_on_hand\": 3");
+		assert_eq!(problems(&text), vec!((Some(10), "cards_on_hand is 3, but the deck only has 1 cards".to_string())));
+	}
+
+	#[test]
+	fn enemies_have_to_stand_on_an_empty_field() {
+		let enemies = "\t\"enemies\": [\n\t\t{\"behaviour\": \"Chaser\", \"x\": 5, \"y\": 0},\n\t\t{\"behaviour\": \"Guard\", \"x\": 1, \"y\": 1}\n\t],\n";
+		assert_eq!(problems(&level_json(&["@.C", ".T."], "\"Key\"", enemies)), vec!(
+			(Some(12), "the Chaser at (5, 0) is outside of the map".to_string()),
+			(Some(13), "the Guard at (1, 1) stands on a Tree".to_string()),
+		));
+	}
+}